@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use programming_language::lexer::Lexer;
+
+fuzz_target!(|src: &str| {
+    let _ = Lexer::new(src).scan_tokens();
+});
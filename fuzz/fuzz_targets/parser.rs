@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use programming_language::{lexer::Lexer, parser::Parser};
+
+fuzz_target!(|src: &str| {
+    // Most inputs do not lex, so only the ones that do reach the parser
+    let Ok(tokens) = Lexer::new(src).scan_tokens() else {
+        return;
+    };
+
+    let _ = Parser::new(tokens.clone()).parse();
+    let _ = Parser::new(tokens).with_lox_compat(true).parse();
+});
@@ -0,0 +1,59 @@
+#ifndef LANG_H
+#define LANG_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct LangInterpreter LangInterpreter;
+
+typedef enum {
+    LANG_NIL = 0,
+    LANG_BOOL = 1,
+    LANG_NUMBER = 2,
+    LANG_STRING = 3,
+} LangValueKind;
+
+typedef struct {
+    LangValueKind kind;
+    bool boolean;
+    double number;
+    const char *string;
+} LangValue;
+
+typedef LangValue (*LangCallback)(void *user_data, const LangValue *args, size_t argc);
+
+LangInterpreter *lang_new(void);
+int lang_eval(LangInterpreter *lang, const char *source);
+const char *lang_get_string_result(const LangInterpreter *lang);
+int lang_register_callback(LangInterpreter *lang, const char *name, size_t arity,
+                           LangCallback callback, void *user_data);
+void lang_free(LangInterpreter *lang);
+
+/* Plugins, loaded with `--plugin path` */
+
+#define LANG_PLUGIN_API_VERSION 2
+
+typedef int (*LangPluginNative)(void *user_data, const LangValue *args, size_t argc,
+                                LangValue *result);
+
+typedef struct {
+    void *context;
+    int (*define_native)(void *context, const char *name, size_t arity,
+                         LangPluginNative native, void *user_data);
+    const char *(*define_script)(void *context, const char *source);
+    void (*fail)(void *context, const char *message);
+} LangPluginHost;
+
+/* Exported by a plugin */
+unsigned int lang_plugin_api_version(void);
+int lang_plugin_register(const LangPluginHost *host);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,270 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    expr::Expr,
+    module::SCRIPT_EXTENSION,
+    program::Program,
+    source::{self, Rewrite},
+    stmt::Stmt,
+    symbols::{SymbolKind, SymbolTable},
+    token::Token,
+};
+
+/// Flattens the script at `path` and the modules it imports, directly or
+/// through other modules, into one self-contained script.
+///
+/// The modules run first, in the order they are first imported. Their
+/// top-level variables, functions and classes are renamed to
+/// `<module>__<name>` so that they cannot collide, and `module.name` is
+/// rewritten to the renamed binding, which is why a bundled module can only
+/// be used to access its members. Imports of modules that are not script
+/// files, e.g. registered by the host, are left as they are.
+pub fn bundle(path: &Path) -> Result<String, String> {
+    let src = fs::read_to_string(path)
+        .map_err(|err| format!("Cannot read '{}': {err}", path.display()))?;
+
+    let mut bundler = Bundler {
+        base_dir: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        members: HashMap::new(),
+        loading: HashSet::new(),
+        modules: String::new(),
+    };
+    let script = bundler.rewrite(&src, None)?;
+
+    return Ok(bundler.modules + &script);
+}
+
+struct Bundler {
+    base_dir: PathBuf,
+    // Top-level names of every module bundled so far
+    members: HashMap<String, HashSet<String>>,
+    loading: HashSet<String>,
+    // Source of the modules bundled so far
+    modules: String,
+}
+
+impl Bundler {
+    /// Bundles the module `name` if it was not already, and returns whether
+    /// it is a script file.
+    fn add_module(&mut self, name: &str) -> Result<bool, String> {
+        if self.members.contains_key(name) {
+            return Ok(true);
+        }
+
+        let path = self.base_dir.join(format!("{name}.{SCRIPT_EXTENSION}"));
+        let Ok(src) = fs::read_to_string(path) else {
+            return Ok(false);
+        };
+
+        if !self.loading.insert(name.to_string()) {
+            return Err(format!("Circular import of module '{name}'"));
+        }
+        let module = self
+            .rewrite(&src, Some(name))
+            .map_err(|err| format!("In module '{name}': {err}"))?;
+        self.loading.remove(name);
+
+        self.modules += &format!("// Module {name}\n{module}\n");
+        return Ok(true);
+    }
+
+    /// Rewrites the source of `module`, or of the script when `None`, after
+    /// bundling the modules it imports.
+    fn rewrite(&mut self, src: &str, module: Option<&str>) -> Result<String, String> {
+        let program = Program::compile(src)?;
+        let symbols = program.symbols();
+
+        let mut imports = vec![];
+        collect_imports(program.stmts(), &mut imports);
+        let mut bundled = HashSet::new();
+        for import in imports {
+            if self.add_module(&import)? {
+                bundled.insert(import);
+            }
+        }
+
+        let renamer = Renamer {
+            module,
+            symbols,
+            bundled: &bundled,
+            members: &self.members,
+            errors: RefCell::new(vec![]),
+        };
+        let source = source::program(program.stmts(), &renamer);
+
+        let errors = renamer.errors.into_inner();
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+
+        match module {
+            Some(module) => {
+                let members = symbols.scopes[0]
+                    .declarations
+                    .iter()
+                    .map(|id| &symbols.declarations[*id])
+                    .filter(|declaration| declaration.kind != SymbolKind::Import)
+                    .map(|declaration| declaration.name.clone())
+                    .collect();
+                self.members.insert(module.to_string(), members);
+            }
+            None => self.check_collisions(symbols)?,
+        }
+
+        return Ok(source);
+    }
+
+    /// Checks that the script does not declare a renamed module binding.
+    fn check_collisions(&self, symbols: &SymbolTable) -> Result<(), String> {
+        for (module, members) in &self.members {
+            for member in members {
+                let name = format!("{module}__{member}");
+                if symbols.global(&name).is_some() {
+                    return Err(format!(
+                        "'{name}' is declared by the script and by module '{module}'"
+                    ));
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+fn collect_imports(stmts: &[Stmt], imports: &mut Vec<String>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Import { name } => imports.push(name.name.clone()),
+            Stmt::Block { statements }
+            | Stmt::Function {
+                body: statements, ..
+            } => collect_imports_boxed(statements, imports),
+            Stmt::IfStmt {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                collect_imports(std::slice::from_ref(then_branch), imports);
+                if let Some(else_branch) = else_branch {
+                    collect_imports(std::slice::from_ref(else_branch), imports);
+                }
+            }
+            Stmt::WhileStmt { body, .. } | Stmt::With { body, .. } => {
+                collect_imports(std::slice::from_ref(body), imports);
+            }
+            Stmt::Class {
+                methods,
+                static_methods,
+                ..
+            }
+            | Stmt::Extend {
+                methods,
+                static_methods,
+                ..
+            } => {
+                collect_imports_boxed(methods, imports);
+                collect_imports_boxed(static_methods, imports);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_imports_boxed(stmts: &[Box<Stmt>], imports: &mut Vec<String>) {
+    for stmt in stmts {
+        collect_imports(std::slice::from_ref(stmt.as_ref()), imports);
+    }
+}
+
+/// Renames the top-level bindings of a module and the accesses to the
+/// members of bundled modules.
+struct Renamer<'a> {
+    // The module being rewritten, `None` for the script
+    module: Option<&'a str>,
+    symbols: &'a SymbolTable,
+    // Modules imported by the source being rewritten that are bundled
+    bundled: &'a HashSet<String>,
+    members: &'a HashMap<String, HashSet<String>>,
+    errors: RefCell<Vec<String>>,
+}
+
+impl Renamer<'_> {
+    /// The new name of the variable `name` of the expression `expr_id`, when
+    /// it refers to a top-level binding of the module.
+    fn renamed(&self, expr_id: usize, name: &Token) -> Option<String> {
+        let module = self.module?;
+        let declaration = self.symbols.definition(expr_id)?;
+
+        if declaration.scope != 0 || declaration.kind == SymbolKind::Import {
+            return None;
+        }
+        return Some(format!("{module}__{}", name.name));
+    }
+
+    /// The module `expr` refers to, when it is a bundled one.
+    fn bundled_module<'e>(&self, expr: &'e Expr) -> Option<&'e str> {
+        let Expr::Variable { id, name } = expr else {
+            return None;
+        };
+
+        let declaration = self.symbols.definition(*id)?;
+        if declaration.kind != SymbolKind::Import || !self.bundled.contains(&name.name) {
+            return None;
+        }
+        return Some(&name.name);
+    }
+
+    fn error(&self, message: String) {
+        self.errors.borrow_mut().push(message);
+    }
+}
+
+impl Rewrite for Renamer<'_> {
+    fn expr(&self, expr: &Expr, level: usize) -> Option<String> {
+        return match expr {
+            Expr::Get { object, name, .. } => {
+                let module = self.bundled_module(object)?;
+                if !self.members[module].contains(&name.name) {
+                    self.error(format!(
+                        "Line {}: Module '{module}' has no member '{}'",
+                        name.line, name.name
+                    ));
+                }
+                Some(format!("{module}__{}", name.name))
+            }
+            Expr::Variable { id, name } => {
+                if let Some(module) = self.bundled_module(expr) {
+                    self.error(format!(
+                        "Line {}: Module '{module}' can only be used to access its members in a bundle",
+                        name.line
+                    ));
+                }
+                self.renamed(*id, name)
+            }
+            Expr::Assign { id, name, value } => {
+                let name = self.renamed(*id, name)?;
+                Some(format!("{name} = {}", source::expr(value, level, self)))
+            }
+            _ => None,
+        };
+    }
+
+    fn stmt(&self, stmt: &Stmt, _level: usize) -> Option<String> {
+        return match stmt {
+            Stmt::Import { name } if self.bundled.contains(&name.name) => Some(String::new()),
+            _ => None,
+        };
+    }
+
+    fn global(&self, name: &Token) -> String {
+        return match self.module {
+            Some(module) => format!("{module}__{}", name.name),
+            None => name.name.clone(),
+        };
+    }
+}
@@ -0,0 +1,96 @@
+//! Script values as seen by C code, shared by the C interface and plugins.
+
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    ptr,
+};
+
+use crate::expr::Literal;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LangValueKind {
+    Nil = 0,
+    Bool = 1,
+    Number = 2,
+    String = 3,
+}
+
+/// A script value crossing the FFI boundary.
+/// Only the field matching `kind` is meaningful.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LangValue {
+    pub kind: LangValueKind,
+    pub boolean: bool,
+    pub number: f64,
+    pub string: *const c_char,
+}
+
+/// Host pointer handed back to callbacks. The host is responsible for any
+/// synchronization when the interpreter is shared between threads.
+#[derive(Clone, Copy)]
+pub(crate) struct UserData(pub(crate) *mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    pub(crate) fn as_ptr(self) -> *mut c_void {
+        return self.0;
+    }
+}
+
+pub(crate) fn to_c_string(string: String) -> CString {
+    return CString::new(string.replace('\0', "")).expect("NUL bytes were removed");
+}
+
+impl LangValue {
+    pub(crate) fn nil() -> Self {
+        return Self {
+            kind: LangValueKind::Nil,
+            boolean: false,
+            number: 0.0,
+            string: ptr::null(),
+        };
+    }
+
+    /// Strings point into `strings`, which has to outlive the returned value.
+    pub(crate) fn from_literal(literal: &Literal, strings: &mut Vec<CString>) -> Self {
+        let mut value = Self::nil();
+
+        match literal {
+            Literal::Nil => (),
+            Literal::True | Literal::False => {
+                value.kind = LangValueKind::Bool;
+                value.boolean = *literal == Literal::True;
+            }
+            Literal::Number(x) => {
+                value.kind = LangValueKind::Number;
+                value.number = *x;
+            }
+            other => {
+                let string = to_c_string(other.to_string());
+                value.kind = LangValueKind::String;
+                value.string = string.as_ptr();
+                strings.push(string);
+            }
+        }
+
+        return value;
+    }
+
+    /// # Safety
+    /// A string value must point to a valid NUL-terminated string.
+    pub(crate) unsafe fn to_literal(self) -> Literal {
+        return match self.kind {
+            LangValueKind::Nil => Literal::Nil,
+            LangValueKind::Bool => Literal::from_bool(self.boolean),
+            LangValueKind::Number => Literal::Number(self.number),
+            LangValueKind::String if self.string.is_null() => Literal::Nil,
+            LangValueKind::String => {
+                Literal::String(CStr::from_ptr(self.string).to_string_lossy().into_owned())
+            }
+        };
+    }
+}
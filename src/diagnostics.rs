@@ -0,0 +1,164 @@
+use std::{collections::HashMap, fmt};
+
+/// A family of problems the resolver reports about valid code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// Local variables, functions and parameters that are never used.
+    Unused,
+    /// Statements after a `return` or an infinite loop.
+    UnreachableCode,
+    /// Conditions that are always true or false, or that assign.
+    ConstantConditions,
+    /// References to names that are not declared anywhere.
+    UndefinedVariables,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 4] = [
+        Lint::Unused,
+        Lint::UnreachableCode,
+        Lint::ConstantConditions,
+        Lint::UndefinedVariables,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Lint::Unused => "unused",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::ConstantConditions => "constant_conditions",
+            Lint::UndefinedVariables => "undefined_variables",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        return Lint::ALL.into_iter().find(|lint| lint.name() == name);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub lint: Lint,
+    pub severity: Severity,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self.severity {
+            Severity::Warning => write!(f, "Warning: {}", self.message),
+            Severity::Error => write!(f, "Error: {}", self.message),
+        };
+    }
+}
+
+/// Diagnostics of a program, ordered by line.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn push(&mut self, lint: Lint, line: usize, message: String) {
+        self.diagnostics.push(Diagnostic {
+            lint,
+            severity: Severity::Warning,
+            line,
+            message,
+        });
+    }
+
+    pub fn sort(&mut self) {
+        self.diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        return self.diagnostics.iter();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.diagnostics.is_empty();
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        return self
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        return self.errors().next().is_some();
+    }
+}
+
+/// The level of every lint, all of them are warnings by default.
+///
+/// On the command line they are set with `-A`, `-W` and `-D` followed by
+/// the name of a lint, or `warnings` for all of them.
+#[derive(Debug, Clone, Default)]
+pub struct Lints {
+    levels: HashMap<Lint, Level>,
+}
+
+impl Lints {
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels.insert(lint, level);
+    }
+
+    /// Sets the level of the lint called `name`, or of all of them for
+    /// `warnings`.
+    pub fn set_by_name(&mut self, name: &str, level: Level) -> Result<(), String> {
+        if name == "warnings" {
+            for lint in Lint::ALL {
+                self.set(lint, level);
+            }
+            return Ok(());
+        }
+
+        return match Lint::from_name(name) {
+            Some(lint) => {
+                self.set(lint, level);
+                Ok(())
+            }
+            None => Err(format!("Unknown lint '{name}'")),
+        };
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        return self.levels.get(&lint).copied().unwrap_or(Level::Warn);
+    }
+
+    /// Drops the allowed diagnostics and turns the denied ones into errors.
+    pub fn apply(&self, diagnostics: &Diagnostics) -> Diagnostics {
+        let diagnostics = diagnostics
+            .iter()
+            .filter_map(|diagnostic| {
+                let severity = match self.level(diagnostic.lint) {
+                    Level::Allow => return None,
+                    Level::Warn => Severity::Warning,
+                    Level::Deny => Severity::Error,
+                };
+
+                return Some(Diagnostic {
+                    severity,
+                    ..diagnostic.clone()
+                });
+            })
+            .collect();
+
+        return Diagnostics { diagnostics };
+    }
+}
@@ -1,51 +1,184 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::collections::{HashMap, HashSet};
 
-use crate::expr::CallableImpl;
-use crate::expr::{Literal, NativeFunctionImpl};
+use crate::explain::Explain;
+use crate::expr::Literal;
+use crate::module::Modules;
+use crate::natives::get_globals;
+use crate::output::Output;
+use crate::permissions::Permissions;
+use crate::sync::{Lock, Shared};
 
 #[derive(Debug, Clone)]
 pub struct Environment {
-    values: Rc<RefCell<HashMap<String, Literal>>>,
-    locals: Rc<RefCell<HashMap<usize, usize>>>,
+    values: Shared<Lock<HashMap<String, Literal>>>,
+    // The natives the global scope starts with
+    builtins: Shared<HashMap<String, Literal>>,
+    locals: Shared<Lock<HashMap<usize, usize>>>,
+    output: Output,
+    modules: Modules,
+    permissions: Permissions,
+    // Whether assigning an undeclared variable fails instead of creating it
+    strict: bool,
+    // Whether code compiled here uses the syntax of the book's Lox
+    lox_compat: bool,
+    explain: Option<Explain>,
     pub enclosing: Option<Box<Environment>>,
 }
 
-fn clock_impl(_args: &[Literal]) -> Literal {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::SystemTime::UNIX_EPOCH)
-        .expect("Could not get system time")
-        .as_millis();
-
-    return Literal::Number(now as f64 / 1000.0);
-}
-
-fn get_globals() -> HashMap<String, Literal> {
-    let mut env = HashMap::new();
-    let name = "clock".to_string();
-
-    let callable_impl = NativeFunctionImpl {
-        name: name.clone(),
-        arity: 0,
-        fun: Rc::new(clock_impl),
-    };
+impl Environment {
+    pub fn new(locals: HashMap<usize, usize>) -> Self {
+        return Self::with_output(locals, Output::default());
+    }
 
-    env.insert(
-        name,
-        Literal::Callable(CallableImpl::NativeFunction(callable_impl)),
-    );
+    pub fn with_output(locals: HashMap<usize, usize>, output: Output) -> Self {
+        return Self::with_permissions(locals, output, Permissions::default());
+    }
 
-    return env;
-}
+    pub fn with_permissions(
+        locals: HashMap<usize, usize>,
+        output: Output,
+        permissions: Permissions,
+    ) -> Self {
+        let builtins = get_globals(permissions);
+        return Self {
+            values: Shared::new(Lock::new(builtins.clone())),
+            builtins: Shared::new(builtins),
+            locals: Shared::new(Lock::new(locals)),
+            output,
+            modules: Modules::default(),
+            permissions,
+            strict: false,
+            lox_compat: false,
+            explain: None,
+            enclosing: None,
+        };
+    }
 
-impl Environment {
-    pub fn new(locals: HashMap<usize, usize>) -> Self {
+    /// Creates an empty global scope sharing this environment's output,
+    /// modules and permissions, e.g. to run an imported script in isolation.
+    pub fn new_global(&self) -> Environment {
         return Self {
-            values: Rc::new(RefCell::new(get_globals())),
-            locals: Rc::new(RefCell::new(locals)),
+            values: Shared::new(Lock::new((*self.builtins).clone())),
+            builtins: self.builtins.clone(),
+            locals: Shared::new(Lock::new(HashMap::new())),
+            output: self.output.clone(),
+            modules: self.modules.clone(),
+            permissions: self.permissions,
+            strict: self.strict,
+            lox_compat: self.lox_compat,
+            explain: self.explain.clone(),
             enclosing: None,
         };
     }
 
+    pub fn output(&self) -> &Output {
+        return &self.output;
+    }
+
+    pub fn modules(&self) -> &Modules {
+        return &self.modules;
+    }
+
+    pub fn permissions(&self) -> Permissions {
+        return self.permissions;
+    }
+
+    /// Makes assignments to variables that were never declared an error,
+    /// by default they create a global.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        return self;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        return self.strict;
+    }
+
+    /// Makes `eval`, imports and [`Program::compile_in`](crate::program::Program::compile_in)
+    /// parse the syntax of the book's Lox.
+    pub fn with_lox_compat(mut self, lox_compat: bool) -> Self {
+        self.lox_compat = lox_compat;
+        return self;
+    }
+
+    pub fn is_lox_compat(&self) -> bool {
+        return self.lox_compat;
+    }
+
+    /// Makes the interpreter print how expressions are evaluated and when
+    /// variables enter and leave scopes, see [`crate::explain`].
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain.then(Explain::default);
+        return self;
+    }
+
+    pub fn explain(&self) -> Option<&Explain> {
+        return self.explain.as_ref();
+    }
+
+    /// Names defined in each local scope, from the outermost to this one.
+    /// The global scope is not included.
+    pub fn scopes(&self) -> Vec<HashSet<String>> {
+        let mut scopes = vec![];
+        let mut environment = self;
+        while let Some(enclosing) = &environment.enclosing {
+            scopes.push(environment.names());
+            environment = enclosing;
+        }
+
+        scopes.reverse();
+        return scopes;
+    }
+
+    /// Values of the variables defined in this scope.
+    pub fn values(&self) -> HashMap<String, Literal> {
+        return self.values.borrow().clone();
+    }
+
+    /// Values of the variables visible from here, excluding the global
+    /// scope. Inner scopes shadow outer ones.
+    pub fn local_values(&self) -> HashMap<String, Literal> {
+        let mut values = HashMap::new();
+        let mut environment = self;
+        while let Some(enclosing) = &environment.enclosing {
+            for (name, value) in environment.values.borrow().iter() {
+                values.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+            environment = enclosing;
+        }
+
+        return values;
+    }
+
+    /// The outermost scope.
+    pub fn global(&self) -> &Environment {
+        let mut environment = self;
+        while let Some(enclosing) = &environment.enclosing {
+            environment = enclosing;
+        }
+
+        return environment;
+    }
+
+    /// Values of the global variables defined by the program, including
+    /// those replacing a native, but not the natives themselves.
+    pub fn user_globals(&self) -> HashMap<String, Literal> {
+        let mut values = self.global().values();
+        values.retain(|name, value| {
+            return !self
+                .builtins
+                .get(name)
+                .is_some_and(|builtin| builtin.is_identical(value));
+        });
+
+        return values;
+    }
+
+    /// Names defined directly in this scope.
+    pub fn names(&self) -> HashSet<String> {
+        return self.values.borrow().keys().cloned().collect();
+    }
+
     pub fn resolve(&mut self, locals: HashMap<usize, usize>) {
         for (k, v) in locals.iter() {
             self.locals.borrow_mut().insert(*k, *v);
@@ -54,8 +187,15 @@ impl Environment {
 
     pub fn enclose(&self) -> Environment {
         return Self {
-            values: Rc::new(RefCell::new(HashMap::new())),
+            values: Shared::new(Lock::new(HashMap::new())),
+            builtins: self.builtins.clone(),
             locals: self.locals.clone(),
+            output: self.output.clone(),
+            modules: self.modules.clone(),
+            permissions: self.permissions,
+            strict: self.strict,
+            lox_compat: self.lox_compat,
+            explain: self.explain.clone(),
             enclosing: Some(Box::new(self.clone())),
         };
     }
@@ -102,6 +242,10 @@ impl Environment {
         }
     }
 
+    pub fn get_global(&self, name: &str) -> Option<Literal> {
+        return self.get_internal(name, None);
+    }
+
     pub fn assign_global(&self, name: &str, value: Literal) -> bool {
         return self.assign_internal(name, value, None);
     }
@@ -111,26 +255,33 @@ impl Environment {
         return self.assign_internal(name, value, distance);
     }
 
+    /// Returns false, without assigning anything, when the variable does not
+    /// exist in strict mode.
     fn assign_internal(&self, name: &str, value: Literal, distance: Option<usize>) -> bool {
         if let Some(distance) = distance {
             if distance == 0 {
-                self.values.borrow_mut().insert(name.to_string(), value);
-                return true;
+                return self.insert(name, value);
             } else {
-                match &self.enclosing {
+                return match &self.enclosing {
                     Some(env) => env.assign_internal(name, value, Some(distance -1)),
                     None => panic!("Tried to resolve a variable that was defined deeper than the current environment depth"),
                 };
-                return true;
             }
         } else {
-            match &self.enclosing {
-                Some(env) => return env.assign_internal(name, value, None),
-                None => match self.values.borrow_mut().insert(name.to_string(), value) {
-                    Some(_) => return true,
-                    None => return false,
-                },
+            return match &self.enclosing {
+                Some(env) => env.assign_internal(name, value, None),
+                None => self.insert(name, value),
             };
         }
     }
+
+    fn insert(&self, name: &str, value: Literal) -> bool {
+        let mut values = self.values.borrow_mut();
+        if self.strict && !values.contains_key(name) {
+            return false;
+        }
+
+        values.insert(name.to_string(), value);
+        return true;
+    }
 }
@@ -0,0 +1,162 @@
+//! Explain mode, which prints how the expressions of every statement reduce
+//! to their values and the variables entering and leaving scopes:
+//!
+//! ``` text
+//! var x = (2 + 3) * y  =>  var x = 5 * 4  =>  var x = 20
+//! + x = 20
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    environment::Environment,
+    expr::{Expr, Literal},
+    source::{self, Rewrite},
+    sync::{Lock, Shared},
+};
+
+const SEPARATOR: &str = "  =>  ";
+
+/// Values of the expressions evaluated by the statements being explained,
+/// one map per statement since a call runs statements within another one.
+/// Values are written down when recorded, as arrays and maps may change
+/// before the statement ends.
+#[derive(Debug, Clone, Default)]
+pub struct Explain {
+    values: Shared<Lock<Vec<HashMap<usize, String>>>>,
+}
+
+impl Explain {
+    /// Starts recording the values of a statement's expressions.
+    pub fn begin(&self) {
+        self.values.borrow_mut().push(HashMap::new());
+    }
+
+    pub fn record(&self, expr_id: usize, value: &Literal) {
+        if !is_shown(value) {
+            return;
+        }
+        if let Some(values) = self.values.borrow_mut().last_mut() {
+            values.insert(expr_id, value.repr());
+        }
+    }
+
+    /// Stops recording and returns the line explaining `exprs`, written
+    /// between `prefix` and `suffix`, or `None` when there was nothing to
+    /// reduce.
+    pub fn end(&self, prefix: &str, exprs: &[&Expr], suffix: &str) -> Option<String> {
+        let values = self.values.borrow_mut().pop().unwrap_or_default();
+
+        let last_round = exprs.iter().map(|expr| round(expr)).max().unwrap_or(0);
+        let mut forms: Vec<String> = vec![];
+        for round in 0..=last_round {
+            let reduced = Reduced {
+                values: &values,
+                round,
+            };
+            let form = exprs
+                .iter()
+                .map(|expr| source::expr(expr, 0, &reduced))
+                .collect::<Vec<String>>()
+                .join(", ");
+            let form = format!("{prefix}{form}{suffix}");
+
+            if forms.last() != Some(&form) {
+                forms.push(form);
+            }
+        }
+
+        if forms.len() < 2 {
+            return None;
+        }
+        return Some(forms.join(SEPARATOR));
+    }
+}
+
+/// Prints the variable `name` entering the scope of `environment`.
+pub fn enter(environment: &Environment, name: &str, value: &Literal) {
+    if environment.explain().is_some() {
+        let line = format!("+ {name} = {}", value.repr());
+        environment.output().write_line(&line);
+    }
+}
+
+/// Prints the variables of `environment` leaving their scope.
+pub fn leave(environment: &Environment) {
+    if environment.explain().is_none() {
+        return;
+    }
+
+    let mut names: Vec<String> = environment.values().into_keys().collect();
+    names.sort();
+    for name in names {
+        environment.output().write_line(&format!("- {name}"));
+    }
+}
+
+/// The round in which `expr` is replaced by its value: literals already are
+/// values, variables are looked up in the first round and every other
+/// expression is reduced the round after its last operand.
+fn round(expr: &Expr) -> usize {
+    let operands: Vec<&Expr> = match expr {
+        Expr::Literal { .. } | Expr::AnonFunction { .. } => return 0,
+        Expr::Variable { .. } | Expr::This { .. } | Expr::Super { .. } => return 1,
+        Expr::Grouping { expression, .. } => return round(expression),
+        Expr::Assign { value, .. } => vec![value],
+        Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+            vec![left, right]
+        }
+        Expr::Unary { right, .. } => vec![right],
+        Expr::Call {
+            callee, arguments, ..
+        } => std::iter::once(callee.as_ref()).chain(arguments).collect(),
+        Expr::Get { object, .. } => vec![object],
+        Expr::Set { object, value, .. } => vec![object, value],
+        Expr::Index { object, index, .. } => vec![object, index],
+        Expr::IndexSet {
+            object,
+            index,
+            value,
+            ..
+        } => vec![object, index, value],
+        Expr::Array { elements, .. } => elements.iter().collect(),
+        Expr::Map { entries, .. } => entries.iter().flat_map(|(k, v)| [k, v]).collect(),
+    };
+
+    return 1 + operands.into_iter().map(round).max().unwrap_or(0);
+}
+
+/// Functions, classes and objects are left as written, their values say
+/// less than the expressions that refer to them.
+fn is_shown(value: &Literal) -> bool {
+    return !matches!(
+        value,
+        Literal::Callable(_)
+            | Literal::Class { .. }
+            | Literal::Instance { .. }
+            | Literal::Module { .. }
+            | Literal::Buffer(_)
+    );
+}
+
+/// Writes expressions with the ones reduced by `round` replaced by their
+/// values. Expressions that did not run, e.g. the right side of a
+/// short-circuiting `and`, stay as written.
+struct Reduced<'a> {
+    values: &'a HashMap<usize, String>,
+    round: usize,
+}
+
+impl Rewrite for Reduced<'_> {
+    fn expr(&self, expr: &Expr, _level: usize) -> Option<String> {
+        if let Expr::AnonFunction { arguments, .. } = expr {
+            let params: Vec<&str> = arguments.iter().map(|arg| arg.name.as_str()).collect();
+            return Some(format!("fun ({}) {{ ... }}", params.join(", ")));
+        }
+
+        if self.round == 0 || round(expr) > self.round {
+            return None;
+        }
+        return self.values.get(&expr.get_id()).cloned();
+    }
+}
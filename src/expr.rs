@@ -1,17 +1,33 @@
-use core::fmt::Debug;
-use std::cell::RefCell;
+use core::fmt::{self, Debug};
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
 
 use crate::environment::Environment;
+use crate::explain;
 use crate::interpreter::Interpreter;
+use crate::natives;
 use crate::stmt::Stmt;
+use crate::sync::{Lock, MaybeSendSync, Shared};
 use crate::token;
 use crate::token::Token;
 use crate::token_type::TokenType;
 
-type CallableFunctionType = Rc<dyn Fn(&[Literal]) -> Literal>;
+#[cfg(not(feature = "sync"))]
+type CallableFunctionType = Shared<dyn Fn(&[Literal]) -> Result<Literal, String>>;
+#[cfg(feature = "sync")]
+type CallableFunctionType = Shared<dyn Fn(&[Literal]) -> Result<Literal, String> + Send + Sync>;
+
+#[cfg(not(feature = "sync"))]
+type EnvFunctionType = Shared<dyn Fn(&[Literal], &Environment) -> Result<Literal, String>>;
+#[cfg(feature = "sync")]
+type EnvFunctionType =
+    Shared<dyn Fn(&[Literal], &Environment) -> Result<Literal, String> + Send + Sync>;
 
 #[derive(Clone)]
 pub struct FunctionImpl {
@@ -25,8 +41,73 @@ pub struct FunctionImpl {
 #[derive(Clone)]
 pub struct NativeFunctionImpl {
     pub name: String,
+    /// Number of required arguments.
     pub arity: usize,
+    /// Number of arguments including the optional ones, `None` when there
+    /// is no limit.
+    pub max_arity: Option<usize>,
     pub fun: CallableFunctionType,
+    /// Set for natives that work on the caller's environment, like `eval`.
+    pub env_fun: Option<EnvFunctionType>,
+}
+
+impl NativeFunctionImpl {
+    pub fn new(
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&[Literal]) -> Result<Literal, String> + MaybeSendSync + 'static,
+    ) -> Self {
+        return Self {
+            name: name.to_string(),
+            arity,
+            max_arity: Some(arity),
+            fun: Shared::new(fun),
+            env_fun: None,
+        };
+    }
+
+    /// A native that receives the environment it is called from. It can
+    /// only be called directly, not passed around as a callback.
+    pub fn with_env(
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&[Literal], &Environment) -> Result<Literal, String> + MaybeSendSync + 'static,
+    ) -> Self {
+        let fun_name = name.to_string();
+        return Self {
+            name: name.to_string(),
+            arity,
+            max_arity: Some(arity),
+            fun: Shared::new(move |_: &[Literal]| {
+                return Err(format!("{fun_name} can only be called directly"));
+            }),
+            env_fun: Some(Shared::new(fun)),
+        };
+    }
+
+    /// Lets the native take optional arguments after the `arity` required
+    /// ones, up to `max_arity` or any number when `None`.
+    pub fn with_max_arity(mut self, max_arity: Option<usize>) -> Self {
+        self.max_arity = max_arity;
+        return self;
+    }
+
+    /// Fails when the native cannot be called with `count` arguments.
+    pub fn check_arity(&self, count: usize) -> Result<(), String> {
+        if count >= self.arity && self.max_arity.is_none_or(|max| count <= max) {
+            return Ok(());
+        }
+
+        let expected = match self.max_arity {
+            Some(max) if max == self.arity => max.to_string(),
+            Some(max) => format!("{} to {max}", self.arity),
+            None => format!("at least {}", self.arity),
+        };
+        return Err(format!(
+            "Callable {} expected {expected} arguments but got {count}",
+            self.name
+        ));
+    }
 }
 
 #[derive(Clone)]
@@ -45,18 +126,32 @@ pub enum Literal {
     Callable(CallableImpl),
     Class {
         name: String,
-        methods: HashMap<String, FunctionImpl>,
+        /// Shared so that `extend` reaches existing instances.
+        methods: Shared<Lock<HashMap<String, FunctionImpl>>>,
+        static_methods: Shared<Lock<HashMap<String, FunctionImpl>>>,
         superclass: Option<Box<Literal>>,
     },
     Instance {
         class: Box<Literal>,
-        fields: Rc<RefCell<Vec<(String, Literal)>>>,
+        fields: Shared<Lock<Vec<(String, Literal)>>>,
     },
+    Module {
+        name: String,
+        members: Shared<HashMap<String, Literal>>,
+    },
+    Array(Shared<Lock<Vec<Literal>>>),
+    /// Entries are kept in insertion order.
+    Map(Shared<Lock<Vec<(Literal, Literal)>>>),
+    DateTime(DateTime<Utc>),
+    /// Exact base 10 number, e.g. `10.50d`.
+    Decimal(Decimal),
+    /// A growable string, see `buffer()`.
+    Buffer(Shared<Lock<String>>),
 }
 
 impl Debug for Literal {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        return write!(f, "{}", self.to_string());
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{self}");
     }
 }
 
@@ -70,9 +165,9 @@ macro_rules! class_name {
     }};
 }
 
-impl ToString for Literal {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = match self {
             Literal::Number(x) => x.to_string(),
             Literal::String(x) => x.to_string(),
             Literal::True => "true".to_string(),
@@ -90,7 +185,25 @@ impl ToString for Literal {
             Literal::Instance { class, fields: _ } => {
                 format!("Instance of '{}'", class_name!(class))
             }
-        }
+            Literal::Module { name, .. } => format!("Module '{name}'"),
+            Literal::Array(elements) => {
+                let elements: Vec<String> = elements.borrow().iter().map(Literal::repr).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Literal::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key.repr(), value.repr()))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Literal::DateTime(x) => x.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            Literal::Buffer(x) => x.borrow().clone(),
+            Literal::Decimal(x) => x.to_string(),
+        };
+
+        return write!(f, "{string}");
     }
 }
 
@@ -122,11 +235,51 @@ impl PartialEq for Literal {
             (Literal::True, Literal::True) => true,
             (Literal::False, Literal::False) => true,
             (Literal::Nil, Literal::Nil) => true,
+            (Literal::Array(x), Literal::Array(y)) => Shared::ptr_eq(x, y),
+            (Literal::Map(x), Literal::Map(y)) => Shared::ptr_eq(x, y),
+            (Literal::DateTime(x), Literal::DateTime(y)) => x == y,
+            (Literal::Buffer(x), Literal::Buffer(y)) => Shared::ptr_eq(x, y),
+            (Literal::Decimal(x), Literal::Decimal(y)) => x == y,
+            (Literal::Decimal(x), Literal::Number(y))
+            | (Literal::Number(y), Literal::Decimal(x)) => to_decimal(*y).is_ok_and(|y| *x == y),
             _ => false,
         };
     }
 }
 
+/// Consistent with `==`: arrays, maps and buffers hash by identity, values that are
+/// never equal to anything only by their type.
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Decimals equal Numbers with the same value
+        if let Literal::Decimal(x) = self {
+            if let Some(x) = x.to_f64() {
+                return Literal::Number(x).hash(state);
+            }
+        }
+
+        self.to_type().hash(state);
+        match self {
+            // 0.0 and -0.0 are equal
+            Literal::Number(x) => (x + 0.0).to_bits().hash(state),
+            Literal::String(x) => x.hash(state),
+            Literal::True | Literal::False | Literal::Nil => self.to_string().hash(state),
+            Literal::Callable(CallableImpl::Function(FunctionImpl { name, arity, .. }))
+            | Literal::Callable(CallableImpl::NativeFunction(NativeFunctionImpl {
+                name,
+                arity,
+                ..
+            })) => (name, arity).hash(state),
+            Literal::Array(x) => Shared::as_ptr(x).hash(state),
+            Literal::Map(x) => Shared::as_ptr(x).hash(state),
+            Literal::DateTime(x) => x.hash(state),
+            Literal::Buffer(x) => Shared::as_ptr(x).hash(state),
+            Literal::Decimal(x) => x.hash(state),
+            Literal::Class { .. } | Literal::Instance { .. } | Literal::Module { .. } => (),
+        }
+    }
+}
+
 fn unwrap_as_f64(literal: Option<token::Literal>) -> f64 {
     if let Some(token::Literal::Number(x)) = literal {
         return x;
@@ -154,12 +307,27 @@ impl Literal {
             Literal::Nil => "Nil",
             Literal::Class { .. } => "Class",
             Literal::Instance { .. } => "Instance",
+            Literal::Module { .. } => "Module",
+            Literal::Array(_) => "Array",
+            Literal::Map(_) => "Map",
+            Literal::DateTime(_) => "DateTime",
+            Literal::Buffer(_) => "Buffer",
+            Literal::Decimal(_) => "Decimal",
+        };
+    }
+
+    /// Representation used inside collections, where strings are quoted.
+    pub fn repr(&self) -> String {
+        return match self {
+            Literal::String(x) => format!("\"{x}\""),
+            other => other.to_string(),
         };
     }
 
     pub fn from_token_literal(literal: token::Literal) -> Self {
         return match literal {
             token::Literal::Number(val) => Self::Number(val),
+            token::Literal::Decimal(val) => Self::Decimal(val),
             token::Literal::String(val) => Self::String(val),
             token::Literal::Identifier(val) => {
                 if val == "true" {
@@ -178,6 +346,10 @@ impl Literal {
     pub fn from_token(token: Token) -> Self {
         match token.token_type {
             TokenType::Number => Self::Number(unwrap_as_f64(token.literal)),
+            TokenType::Decimal => match token.literal {
+                Some(token::Literal::Decimal(x)) => Self::Decimal(x),
+                _ => panic!("Could not unwrap as decimal"),
+            },
             TokenType::String => Self::String(unwrap_as_string(token.literal)),
             TokenType::False => Self::False,
             TokenType::True => Self::True,
@@ -186,6 +358,222 @@ impl Literal {
         }
     }
 
+    /// Reads the field or method `name`, as `value.name` does.
+    pub fn get_property(&self, name: &str) -> Result<Literal, String> {
+        if let Literal::Module {
+            name: module_name,
+            members,
+        } = self
+        {
+            return match members.get(name) {
+                Some(value) => Ok(value.clone()),
+                None => Err(format!("Module '{module_name}' has no member '{name}'")),
+            };
+        }
+
+        if let Literal::Class {
+            name: class_name, ..
+        } = self
+        {
+            let mut class = Some(self);
+            while let Some(Literal::Class {
+                static_methods,
+                superclass,
+                ..
+            }) = class
+            {
+                if let Some(method) = static_methods.borrow().get(name) {
+                    return Ok(Literal::Callable(CallableImpl::Function(method.clone())));
+                }
+                class = superclass.as_deref();
+            }
+
+            return Err(format!(
+                "Class '{class_name}' has no static method '{name}'"
+            ));
+        }
+
+        if let Literal::Instance { class, fields } = self.clone() {
+            for (field_name, value) in fields.borrow().iter() {
+                if *field_name == name {
+                    return Ok(value.clone());
+                }
+            }
+
+            if let Literal::Class {
+                methods,
+                superclass,
+                ..
+            } = class.as_ref()
+            {
+                let method = methods.borrow().get(name).cloned();
+                if let Some(method) = method {
+                    let mut callable_impl = method;
+                    let new_env = callable_impl.parent_env.enclose();
+                    new_env.define("this".to_string(), self.clone());
+                    callable_impl.parent_env = new_env;
+                    return Ok(Literal::Callable(CallableImpl::Function(callable_impl)));
+                } else if let Some(superclass) = superclass {
+                    if let Literal::Class { methods, .. } = superclass.as_ref() {
+                        if let Some(method) = methods.borrow().get(name) {
+                            let mut callable_impl = method.clone();
+                            let new_env = callable_impl.parent_env.enclose();
+                            new_env.define("this".to_string(), self.clone());
+                            callable_impl.parent_env = new_env;
+                            return Ok(Literal::Callable(CallableImpl::Function(callable_impl)));
+                        }
+                    }
+                }
+            } else {
+                panic!("The class field on an instance was not a Class");
+            }
+
+            return Err(format!("No field named '{name}' on this instance"));
+        } else if let Some(method) = natives::get_method(self, name) {
+            return Ok(method);
+        } else {
+            return Err(format!(
+                "No method named '{name}' on type '{}'",
+                self.to_type()
+            ));
+        }
+    }
+
+    /// Whether both are the same native function or module, not just ones
+    /// with the same name.
+    pub fn is_identical(&self, other: &Literal) -> bool {
+        return match (self, other) {
+            (
+                Literal::Callable(CallableImpl::NativeFunction(x)),
+                Literal::Callable(CallableImpl::NativeFunction(y)),
+            ) => Shared::ptr_eq(&x.fun, &y.fun),
+            (Literal::Module { members: x, .. }, Literal::Module { members: y, .. }) => {
+                Shared::ptr_eq(x, y)
+            }
+            _ => false,
+        };
+    }
+
+    /// Whether this is an array, map or instance frozen by `freeze()`.
+    pub fn is_frozen(&self) -> bool {
+        return match self {
+            Literal::Array(elements) => elements.is_frozen(),
+            Literal::Map(entries) => entries.is_frozen(),
+            Literal::Instance { fields, .. } => fields.is_frozen(),
+            _ => false,
+        };
+    }
+
+    /// Fails when this is frozen, before a script modifies it.
+    pub fn check_mutable(&self) -> Result<(), String> {
+        if !self.is_frozen() {
+            return Ok(());
+        }
+
+        return match self {
+            Literal::Instance { .. } => Err(format!("Cannot modify a frozen {self}")),
+            _ => Err(format!("Cannot modify a frozen {}", self.to_type())),
+        };
+    }
+
+    /// Creates or updates the field `name` of an instance.
+    pub fn set_property(&self, name: &str, value: Literal) -> Result<(), String> {
+        let Literal::Instance { fields, .. } = self else {
+            return Err(format!("Cannot access property on type '{}'", self));
+        };
+        self.check_mutable()?;
+
+        let mut fields = fields.borrow_mut();
+        match fields.iter_mut().find(|(field_name, _)| field_name == name) {
+            Some(field) => field.1 = value,
+            None => fields.push((name.to_string(), value)),
+        }
+
+        return Ok(());
+    }
+
+    /// Looks up `key` when this is a map.
+    pub fn map_get(&self, key: &Literal) -> Option<Literal> {
+        if let Literal::Map(entries) = self {
+            for (entry_key, value) in entries.borrow().iter() {
+                if entry_key == key {
+                    return Some(value.clone());
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// Inserts or updates `key` when this is a map. New keys go last.
+    pub fn map_insert(&self, key: Literal, value: Literal) {
+        if let Literal::Map(entries) = self {
+            let mut entries = entries.borrow_mut();
+            match entries.iter_mut().find(|(entry_key, _)| *entry_key == key) {
+                Some(entry) => entry.1 = value,
+                None => entries.push((key, value)),
+            }
+        }
+    }
+
+    /// Like [`Literal::call`], but gives natives access to `environment`.
+    pub fn call_in(
+        &self,
+        arguments: Vec<Literal>,
+        environment: &Environment,
+    ) -> Result<Literal, String> {
+        if let Literal::Callable(CallableImpl::NativeFunction(
+            native_fun @ NativeFunctionImpl {
+                env_fun: Some(env_fun),
+                ..
+            },
+        )) = self
+        {
+            native_fun.check_arity(arguments.len())?;
+            return env_fun(&arguments, environment);
+        }
+
+        return self.call(arguments);
+    }
+
+    /// Calls a function, native function or class with already evaluated
+    /// arguments.
+    pub fn call(&self, arguments: Vec<Literal>) -> Result<Literal, String> {
+        match self {
+            Literal::Callable(CallableImpl::Function(fun)) => {
+                return run_function(fun.clone(), arguments);
+            }
+            Literal::Callable(CallableImpl::NativeFunction(native_fun)) => {
+                native_fun.check_arity(arguments.len())?;
+                return (native_fun.fun)(&arguments);
+            }
+            Literal::Class { methods, .. } => {
+                let instance = Literal::Instance {
+                    class: Box::new(self.clone()),
+                    fields: Shared::new(Lock::new(vec![])),
+                };
+
+                let constructor = methods.borrow().get("init").cloned();
+                if let Some(constructor) = constructor {
+                    if constructor.arity != arguments.len() {
+                        return Err("Invalid number of arguments in constructor".to_string());
+                    }
+
+                    let mut constructor = constructor;
+                    constructor.parent_env = constructor.parent_env.enclose();
+                    constructor
+                        .parent_env
+                        .define("this".to_string(), instance.clone());
+
+                    run_function(constructor, arguments)?;
+                }
+
+                return Ok(instance);
+            }
+            other => return Err(format!("{} is not callable", other)),
+        };
+    }
+
     pub fn from_bool(b: bool) -> Self {
         if b {
             return Literal::True;
@@ -210,6 +598,11 @@ impl Literal {
                     Literal::False
                 }
             }
+            Literal::Array(x) => Literal::from_bool(x.borrow().is_empty()),
+            Literal::Map(x) => Literal::from_bool(x.borrow().is_empty()),
+            Literal::DateTime(_) | Literal::Module { .. } => Literal::False,
+            Literal::Buffer(x) => Literal::from_bool(x.borrow().is_empty()),
+            Literal::Decimal(x) => Literal::from_bool(x.is_zero()),
             Literal::True => Literal::False,
             Literal::False => Literal::True,
             Literal::Nil => Literal::False,
@@ -235,6 +628,11 @@ impl Literal {
                     Literal::True
                 }
             }
+            Literal::Array(x) => Literal::from_bool(!x.borrow().is_empty()),
+            Literal::Map(x) => Literal::from_bool(!x.borrow().is_empty()),
+            Literal::DateTime(_) | Literal::Module { .. } => Literal::True,
+            Literal::Buffer(x) => Literal::from_bool(!x.borrow().is_empty()),
+            Literal::Decimal(x) => Literal::from_bool(!x.is_zero()),
             Literal::True => Literal::True,
             Literal::False => Literal::False,
             Literal::Nil => Literal::True,
@@ -253,6 +651,14 @@ pub enum Expr {
         arguments: Vec<Token>,
         body: Vec<Box<Stmt>>,
     },
+    Array {
+        id: usize,
+        elements: Vec<Expr>,
+    },
+    Map {
+        id: usize,
+        entries: Vec<(Expr, Expr)>,
+    },
     Assign {
         id: usize,
         name: Token,
@@ -279,6 +685,19 @@ pub enum Expr {
         id: usize,
         expression: Box<Expr>,
     },
+    Index {
+        id: usize,
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    IndexSet {
+        id: usize,
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
     Literal {
         id: usize,
         value: Literal,
@@ -331,24 +750,17 @@ impl PartialEq for Expr {
 
 impl Eq for Expr {}
 
-impl ToString for Expr {
-    fn to_string(&self) -> String {
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Expr::Assign { id: _, name, value } => {
-                return format!("({:?} = {})", name, (*value).to_string())
-            }
+            Expr::Assign { id: _, name, value } => return write!(f, "({:?} = {})", name, (*value)),
             Expr::Binary {
                 id: _,
                 left,
                 operator,
                 right,
             } => {
-                return format!(
-                    "({} {} {})",
-                    &operator.name,
-                    (*left).to_string(),
-                    (*right).to_string()
-                );
+                return write!(f, "({} {} {})", &operator.name, (*left), (*right));
             }
             Expr::Call {
                 id: _,
@@ -356,59 +768,62 @@ impl ToString for Expr {
                 paren: _,
                 arguments,
             } => {
-                return format!("({}, {:?})", (*callee).to_string(), arguments);
+                return write!(f, "({}, {:?})", (*callee), arguments);
             }
             Expr::Grouping { id: _, expression } => {
-                return format!("(group {})", (*expression).to_string());
+                return write!(f, "(group {})", (*expression));
             }
             Expr::Literal { id: _, value } => {
-                return value.to_string();
+                return write!(f, "{value}");
             }
             Expr::Unary {
                 id: _,
                 operator,
                 right,
             } => {
-                return format!("({} {})", &operator.name, (*right).to_string());
+                return write!(f, "({} {})", &operator.name, (*right));
             }
-            Expr::Variable { id: _, name } => format!("(var {})", name.name),
+            Expr::Variable { id: _, name } => write!(f, "(var {})", name.name),
             Expr::Logical {
                 id: _,
                 left,
                 operator,
                 right,
-            } => {
-                return format!(
-                    "({} {} {})",
-                    operator.to_string(),
-                    left.to_string(),
-                    right.to_string()
-                )
-            }
+            } => return write!(f, "({} {} {})", operator, left, right),
             Expr::AnonFunction {
                 id: _,
                 paren: _,
                 arguments,
                 body: _,
-            } => format!("anon|{}", arguments.len()),
+            } => write!(f, "anon|{}", arguments.len()),
             Expr::Get {
                 id: _,
                 object,
                 name,
-            } => format!("(get {} {})", object.to_string(), name.name),
+            } => write!(f, "(get {} {})", object, name.name),
             Expr::Set {
                 id: _,
                 object,
                 name,
                 value,
-            } => format!(
-                "set {} {} {})",
-                object.to_string(),
-                name.to_string(),
-                value.to_string()
-            ),
-            Expr::This { .. } => "(this)".to_string(),
-            Expr::Super { .. } => "(super)".to_string(),
+            } => write!(f, "set {} {} {})", object, name, value),
+            Expr::Array { id: _, elements } => write!(f, "(array {elements:?})"),
+            Expr::Map { id: _, entries } => write!(f, "(map {entries:?})"),
+            Expr::Index {
+                id: _,
+                object,
+                bracket: _,
+                index,
+            } => write!(f, "(index {object} {index})"),
+            Expr::IndexSet {
+                id: _,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => write!(f, "(index-set {object} {index} {value})"),
+            Expr::This { .. } => write!(f, "(this)"),
+            Expr::Super { .. } => write!(f, "(super)"),
         }
     }
 }
@@ -417,11 +832,15 @@ impl Expr {
     pub fn get_id(&self) -> usize {
         return match self {
             Expr::AnonFunction { id, .. } => *id,
+            Expr::Array { id, .. } => *id,
+            Expr::Map { id, .. } => *id,
             Expr::Assign { id, .. } => *id,
             Expr::Binary { id, .. } => *id,
             Expr::Call { id, .. } => *id,
             Expr::Get { id, .. } => *id,
             Expr::Grouping { id, .. } => *id,
+            Expr::Index { id, .. } => *id,
+            Expr::IndexSet { id, .. } => *id,
             Expr::Literal { id, .. } => *id,
             Expr::Logical { id, .. } => *id,
             Expr::Unary { id, .. } => *id,
@@ -433,6 +852,16 @@ impl Expr {
     }
 
     pub fn evaluate(&self, environment: Environment) -> Result<Literal, String> {
+        let explain = environment.explain().cloned();
+        let value = self.evaluate_node(environment)?;
+
+        if let Some(explain) = explain {
+            explain.record(self.get_id(), &value);
+        }
+        return Ok(value);
+    }
+
+    fn evaluate_node(&self, environment: Environment) -> Result<Literal, String> {
         return match self {
             Expr::AnonFunction {
                 id: _,
@@ -458,50 +887,7 @@ impl Expr {
                 object,
                 name,
             } => {
-                let obj_value = object.evaluate(environment)?;
-                if let Literal::Instance { class, fields } = obj_value.clone() {
-                    for (field_name, value) in fields.borrow().iter() {
-                        if *field_name == name.name {
-                            return Ok(value.clone());
-                        }
-                    }
-
-                    if let Literal::Class {
-                        methods,
-                        superclass,
-                        ..
-                    } = class.as_ref()
-                    {
-                        if let Some(method) = methods.get(&name.name) {
-                            let mut callable_impl = method.clone();
-                            let new_env = callable_impl.parent_env.enclose();
-                            new_env.define("this".to_string(), obj_value);
-                            callable_impl.parent_env = new_env;
-                            return Ok(Literal::Callable(CallableImpl::Function(callable_impl)));
-                        } else if let Some(superclass) = superclass {
-                            if let Literal::Class { methods, .. } = superclass.as_ref() {
-                                if let Some(method) = methods.get(&name.name) {
-                                    let mut callable_impl = method.clone();
-                                    let new_env = callable_impl.parent_env.enclose();
-                                    new_env.define("this".to_string(), obj_value);
-                                    callable_impl.parent_env = new_env;
-                                    return Ok(Literal::Callable(CallableImpl::Function(
-                                        callable_impl,
-                                    )));
-                                }
-                            }
-                        }
-                    } else {
-                        panic!("The class field on an instance was not a Class");
-                    }
-
-                    return Err(format!("No field named '{}' on this instance", name.name));
-                } else {
-                    return Err(format!(
-                        "Cannot access property on type '{}'",
-                        obj_value.to_string()
-                    ));
-                }
+                return object.evaluate(environment)?.get_property(&name.name);
             }
             Expr::Set {
                 id: _,
@@ -510,33 +896,80 @@ impl Expr {
                 value,
             } => {
                 let obj_value = object.evaluate(environment.clone())?;
+                if !matches!(obj_value, Literal::Instance { .. }) {
+                    return Err(format!("Cannot access property on type '{}'", obj_value));
+                }
 
-                if let Literal::Instance { class: _, fields } = obj_value {
-                    let value = value.evaluate(environment)?;
+                let value = value.evaluate(environment)?;
+                obj_value.set_property(&name.name, value)?;
+                return Ok(Literal::Nil);
+            }
+            Expr::Array { id: _, elements } => {
+                let mut values = vec![];
+                for element in elements {
+                    values.push(element.evaluate(environment.clone())?);
+                }
 
-                    let mut idx = 0;
-                    let mut found = false;
+                return Ok(Literal::Array(Shared::new(Lock::new(values))));
+            }
+            Expr::Map { id: _, entries } => {
+                let map = Literal::Map(Shared::new(Lock::new(vec![])));
+                for (key, value) in entries {
+                    let key = key.evaluate(environment.clone())?;
+                    let value = value.evaluate(environment.clone())?;
+                    map.map_insert(key, value);
+                }
 
-                    for i in 0..fields.borrow().len() {
-                        let field_name = &fields.borrow()[i].0;
-                        if field_name == &name.name {
-                            idx = i;
-                            found = true;
-                            break;
-                        }
-                    }
+                return Ok(map);
+            }
+            Expr::Index {
+                id: _,
+                object,
+                bracket: _,
+                index,
+            } => {
+                let obj_value = object.evaluate(environment.clone())?;
+                let index = index.evaluate(environment)?;
 
-                    if found {
-                        fields.borrow_mut()[idx].1 = value;
-                    } else {
-                        fields.borrow_mut().push((name.name.clone(), value));
+                return match obj_value {
+                    Literal::Array(elements) => {
+                        let elements = elements.borrow();
+                        let idx = array_index(&index, elements.len())?;
+                        Ok(elements[idx].clone())
+                    }
+                    Literal::String(x) => {
+                        let chars: Vec<char> = x.chars().collect();
+                        let idx = array_index(&index, chars.len())?;
+                        Ok(Literal::String(chars[idx].to_string()))
                     }
+                    Literal::Map(_) => Ok(obj_value.map_get(&index).unwrap_or(Literal::Nil)),
+                    other => Err(format!("Cannot index into type '{}'", other.to_type())),
+                };
+            }
+            Expr::IndexSet {
+                id: _,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => {
+                let obj_value = object.evaluate(environment.clone())?;
+                let index = index.evaluate(environment.clone())?;
+                let value = value.evaluate(environment)?;
+                obj_value.check_mutable()?;
 
-                    return Ok(Literal::Nil);
+                if let Literal::Array(elements) = obj_value {
+                    let mut elements = elements.borrow_mut();
+                    let idx = array_index(&index, elements.len())?;
+                    elements[idx] = value.clone();
+                    return Ok(value);
+                } else if let Literal::Map(_) = obj_value {
+                    obj_value.map_insert(index, value.clone());
+                    return Ok(value);
                 } else {
                     return Err(format!(
-                        "Cannot access property on type '{}'",
-                        obj_value.to_string()
+                        "Cannot assign to an index of type '{}'",
+                        obj_value.to_type()
                     ));
                 }
             }
@@ -570,6 +1003,7 @@ impl Expr {
 
                 return match (right.clone(), operator.token_type) {
                     (Literal::Number(x), TokenType::Minus) => Ok(Literal::Number(-x)),
+                    (Literal::Decimal(x), TokenType::Minus) => Ok(Literal::Decimal(-x)),
                     (_, TokenType::Minus) => Err(format!("Minus not implemented for {right:?}")),
                     (any, TokenType::Bang) => Ok(any.is_falsey()),
                     (_, token_type) => Err(format!("{token_type:?} is not a valid unary operator")),
@@ -582,44 +1016,13 @@ impl Expr {
                 arguments,
             } => {
                 let callable = (*callee).evaluate(environment.clone())?;
-                match callable.clone() {
-                    Literal::Callable(CallableImpl::Function(fun)) => {
-                        return run_function(fun, arguments, environment);
-                    }
-                    Literal::Callable(CallableImpl::NativeFunction(native_fun)) => {
-                        let mut evaluated_arguments = vec![];
-                        for arg in arguments {
-                            evaluated_arguments.push(arg.evaluate(environment.clone())?);
-                        }
 
-                        return Ok((native_fun.fun)(&evaluated_arguments));
-                    }
-                    Literal::Class { methods, .. } => {
-                        let instance = Literal::Instance {
-                            class: Box::new(callable),
-                            fields: Rc::new(RefCell::new(vec![])),
-                        };
-
-                        if let Some(constructor) = methods.get("init") {
-                            if constructor.arity != arguments.len() {
-                                return Err(
-                                    "Invalid number of arguments in constructor".to_string()
-                                );
-                            }
-
-                            let mut constructor = constructor.clone();
-                            constructor.parent_env = constructor.parent_env.enclose();
-                            constructor
-                                .parent_env
-                                .define("this".to_string(), instance.clone());
-
-                            run_function(constructor, arguments, environment)?;
-                        }
+                let mut evaluated_arguments = vec![];
+                for arg in arguments {
+                    evaluated_arguments.push(arg.evaluate(environment.clone())?);
+                }
 
-                        return Ok(instance);
-                    }
-                    other => return Err(format!("{} is not callable", other.to_string())),
-                };
+                return callable.call_in(evaluated_arguments, &environment);
             }
             Expr::Logical {
                 id: _,
@@ -713,6 +1116,22 @@ impl Expr {
                         return Ok(Literal::from_bool(l <= r));
                     }
 
+                    (Literal::Decimal(l), op, Literal::Decimal(r)) if !is_equality(op) => {
+                        return decimal_binary(l, op, r);
+                    }
+                    (Literal::Decimal(l), op, Literal::Number(r)) if !is_equality(op) => {
+                        return decimal_binary(l, op, to_decimal(r)?);
+                    }
+                    (Literal::Number(l), op, Literal::Decimal(r)) if !is_equality(op) => {
+                        return decimal_binary(to_decimal(l)?, op, r);
+                    }
+                    (Literal::String(l), TokenType::Plus, Literal::Decimal(r)) => {
+                        return Ok(Literal::String(format!("{l}{r}")));
+                    }
+                    (Literal::Decimal(l), TokenType::Plus, Literal::String(r)) => {
+                        return Ok(Literal::String(format!("{l}{r}")));
+                    }
+
                     (l, TokenType::EqualEqual, r) => {
                         return Ok(Literal::from_bool(l == r));
                     }
@@ -742,7 +1161,7 @@ impl Expr {
                     .expect("Couldn't lookup 'this'");
 
                 if let Literal::Class { methods, .. } = superclass {
-                    if let Some(method_value) = methods.get(&method.name) {
+                    if let Some(method_value) = methods.borrow().get(&method.name) {
                         let mut method = method_value.clone();
                         method.parent_env = method.parent_env.enclose();
                         method.parent_env.define("this".to_string(), instance);
@@ -751,17 +1170,55 @@ impl Expr {
                         return Err(format!("Method {} not found", method.name));
                     }
                 } else {
-                    panic!("The superclass field of a class should be a class");
+                    return Err("Cannot use 'super' in a class without a superclass".to_string());
                 }
             }
         };
     }
 }
-pub fn run_function(
-    fun: FunctionImpl,
-    arguments: &Vec<Expr>,
-    eval_env: Environment,
-) -> Result<Literal, String> {
+fn is_equality(op: TokenType) -> bool {
+    return op == TokenType::EqualEqual || op == TokenType::BangEqual;
+}
+
+/// Converts a Number to a Decimal, e.g. to mix both in arithmetic.
+pub fn to_decimal(x: f64) -> Result<Decimal, String> {
+    return Decimal::from_f64(x).ok_or_else(|| format!("Cannot convert {x} to a Decimal"));
+}
+
+fn decimal_binary(l: Decimal, op: TokenType, r: Decimal) -> Result<Literal, String> {
+    let result = match op {
+        TokenType::Plus => l.checked_add(r),
+        TokenType::Minus => l.checked_sub(r),
+        TokenType::Star => l.checked_mul(r),
+        TokenType::Slash if r.is_zero() => return Err("Division by zero".to_string()),
+        TokenType::Slash => l.checked_div(r),
+        TokenType::Greater => return Ok(Literal::from_bool(l > r)),
+        TokenType::GreaterEqual => return Ok(Literal::from_bool(l >= r)),
+        TokenType::Less => return Ok(Literal::from_bool(l < r)),
+        TokenType::LessEqual => return Ok(Literal::from_bool(l <= r)),
+        op => return Err(format!("{op:?} is not implemented for decimals")),
+    };
+
+    return match result {
+        Some(x) => Ok(Literal::Decimal(x)),
+        None => Err(format!("Decimal overflow in {l} {op:?} {r}")),
+    };
+}
+
+/// Converts `index` to a position inside a sequence of length `len`.
+pub fn array_index(index: &Literal, len: usize) -> Result<usize, String> {
+    if let Literal::Number(x) = index {
+        if x.fract() == 0.0 && *x >= 0.0 && (*x as usize) < len {
+            return Ok(*x as usize);
+        }
+
+        return Err(format!("Index {x} out of bounds for length {len}"));
+    }
+
+    return Err(format!("Index must be a Number, not '{}'", index.to_type()));
+}
+
+pub fn run_function(fun: FunctionImpl, arguments: Vec<Literal>) -> Result<Literal, String> {
     if arguments.len() != fun.arity {
         return Err(format!(
             "Callable {} expected {} arguments but got {}",
@@ -771,25 +1228,24 @@ pub fn run_function(
         ));
     }
 
-    let mut args_val = vec![];
-    for arg in arguments {
-        args_val.push(arg.evaluate(eval_env.clone())?);
-    }
-
     let fun_env = fun.parent_env.enclose();
 
-    for (i, val) in args_val.iter().enumerate() {
-        fun_env.define(fun.params.get(i).unwrap().name.clone(), val.clone());
+    for (param, val) in fun.params.iter().zip(&arguments) {
+        explain::enter(&fun_env, &param.name, val);
+        fun_env.define(param.name.clone(), val.clone());
     }
 
     let mut int = Interpreter::with_env(fun_env);
-    for i in 0..fun.body.len() {
-        int.interpret(vec![fun.body.get(i).unwrap()])?;
+    let mut result = Literal::Nil;
+    for stmt in &fun.body {
+        int.execute(stmt)?;
 
         if let Some(value) = int.specials.get("return") {
-            return Ok(value.clone());
+            result = value.clone();
+            break;
         }
     }
 
-    return Ok(Literal::Nil);
+    explain::leave(&int.environment);
+    return Ok(result);
 }
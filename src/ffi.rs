@@ -0,0 +1,132 @@
+//! C interface for embedding the interpreter in non-Rust hosts.
+//! The matching declarations live in `include/lang.h`.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+pub use crate::c_value::{LangValue, LangValueKind};
+use crate::{
+    c_value::{to_c_string, UserData},
+    expr::Literal,
+    interpreter::Interpreter,
+    output::Output,
+};
+
+pub struct LangInterpreter {
+    interpreter: Interpreter,
+    output: Output,
+    result: CString,
+}
+
+pub type LangCallback =
+    extern "C" fn(user_data: *mut c_void, args: *const LangValue, argc: usize) -> LangValue;
+
+/// Creates a new interpreter. Release it with `lang_free`.
+#[no_mangle]
+pub extern "C" fn lang_new() -> *mut LangInterpreter {
+    let output = Output::buffer();
+    let lang = LangInterpreter {
+        interpreter: Interpreter::with_output(output.clone()),
+        output,
+        result: CString::default(),
+    };
+
+    return Box::into_raw(Box::new(lang));
+}
+
+/// Runs `source` and returns 0 on success, 1 on a script error and -1 on
+/// invalid arguments or an internal failure. The printed output (or the
+/// error message) is available through `lang_get_string_result`.
+///
+/// # Safety
+/// `lang` must come from `lang_new` and `source` must be a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lang_eval(lang: *mut LangInterpreter, source: *const c_char) -> c_int {
+    let Some(lang) = lang.as_mut() else {
+        return -1;
+    };
+    if source.is_null() {
+        return -1;
+    }
+
+    let source = CStr::from_ptr(source).to_string_lossy();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| lang.interpreter.run(&source)));
+    let output = lang.output.take();
+
+    let (status, result) = match result {
+        Ok(Ok(_)) => (0, output),
+        Ok(Err(err)) => (1, err),
+        Err(_) => (-1, "Internal interpreter error".to_string()),
+    };
+
+    lang.result = to_c_string(result);
+    return status;
+}
+
+/// Returns the result of the last `lang_eval` call. The string is owned by
+/// the interpreter and stays valid until the next `lang_eval` or `lang_free`.
+///
+/// # Safety
+/// `lang` must come from `lang_new`.
+#[no_mangle]
+pub unsafe extern "C" fn lang_get_string_result(lang: *const LangInterpreter) -> *const c_char {
+    return match lang.as_ref() {
+        Some(lang) => lang.result.as_ptr(),
+        None => ptr::null(),
+    };
+}
+
+/// Defines a global native function `name` that forwards its arguments to
+/// `callback`. String arguments are only valid during the call, and a
+/// returned string is copied before the callback's memory is touched again.
+/// Returns 0 on success and -1 on invalid arguments.
+///
+/// # Safety
+/// `lang` must come from `lang_new`, `name` must be a valid NUL-terminated
+/// string and `user_data` must stay valid for as long as the interpreter lives.
+#[no_mangle]
+pub unsafe extern "C" fn lang_register_callback(
+    lang: *mut LangInterpreter,
+    name: *const c_char,
+    arity: usize,
+    callback: Option<LangCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    let (Some(lang), Some(callback)) = (lang.as_mut(), callback) else {
+        return -1;
+    };
+    if name.is_null() {
+        return -1;
+    }
+
+    let name = CStr::from_ptr(name).to_string_lossy();
+    let user_data = UserData(user_data);
+    lang.interpreter
+        .define_native(&name, arity, move |args: &[Literal]| {
+            let mut strings = vec![];
+            let values: Vec<LangValue> = args
+                .iter()
+                .map(|arg| LangValue::from_literal(arg, &mut strings))
+                .collect();
+
+            let result = callback(user_data.as_ptr(), values.as_ptr(), values.len());
+            return Ok(unsafe { result.to_literal() });
+        });
+
+    return 0;
+}
+
+/// Destroys an interpreter created with `lang_new`.
+///
+/// # Safety
+/// `lang` must come from `lang_new` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn lang_free(lang: *mut LangInterpreter) {
+    if !lang.is_null() {
+        drop(Box::from_raw(lang));
+    }
+}
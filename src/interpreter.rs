@@ -1,9 +1,17 @@
 use std::collections::HashMap;
 
 use crate::{
+    diagnostics::Lints,
     environment::Environment,
-    expr::{CallableImpl, FunctionImpl, Literal},
+    explain,
+    expr::{CallableImpl, Expr, FunctionImpl, Literal, NativeFunctionImpl},
+    natives,
+    output::Output,
+    permissions::Permissions,
+    program::Program,
+    stepper::Stepper,
     stmt::Stmt,
+    sync::{Lock, MaybeSendSync, Shared},
     token::Token,
 };
 
@@ -11,6 +19,7 @@ use crate::{
 pub struct Interpreter {
     pub specials: HashMap<String, Literal>,
     pub environment: Environment,
+    lints: Lints,
 }
 
 impl Default for Interpreter {
@@ -19,14 +28,194 @@ impl Default for Interpreter {
     }
 }
 
+/// Configures an [`Interpreter`], e.g. to capture its output or to grant
+/// permissions beyond the sandboxed defaults.
+#[derive(Debug, Clone, Default)]
+pub struct InterpreterBuilder {
+    output: Output,
+    permissions: Permissions,
+    lints: Lints,
+    strict: bool,
+    lox_compat: bool,
+    explain: bool,
+}
+
+impl InterpreterBuilder {
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        return self;
+    }
+
+    pub fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        return self;
+    }
+
+    pub fn allow_set_env(mut self, allow: bool) -> Self {
+        self.permissions.set_env = allow;
+        return self;
+    }
+
+    pub fn allow_exec(mut self, allow: bool) -> Self {
+        self.permissions.exec = allow;
+        return self;
+    }
+
+    pub fn allow_eval(mut self, allow: bool) -> Self {
+        self.permissions.eval = allow;
+        return self;
+    }
+
+    pub fn allow_fs_write(mut self, allow: bool) -> Self {
+        self.permissions.fs_write = allow;
+        return self;
+    }
+
+    /// Makes assigning a variable that was never declared an error instead
+    /// of creating a global.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        return self;
+    }
+
+    /// Parses programs with the syntax of the Lox language from "Crafting
+    /// Interpreters": `print value;` and `class B < A`.
+    pub fn lox_compat(mut self, lox_compat: bool) -> Self {
+        self.lox_compat = lox_compat;
+        return self;
+    }
+
+    /// Prints how the expressions of every statement are evaluated and the
+    /// variables entering and leaving scopes, to teach evaluation order.
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        return self;
+    }
+
+    /// Decides which diagnostics stop [`Interpreter::run`] from running a
+    /// program.
+    pub fn lints(mut self, lints: Lints) -> Self {
+        self.lints = lints;
+        return self;
+    }
+
+    pub fn build(self) -> Interpreter {
+        let mut interpreter = Interpreter::with_env(
+            Environment::with_permissions(HashMap::new(), self.output, self.permissions)
+                .with_strict(self.strict)
+                .with_lox_compat(self.lox_compat)
+                .with_explain(self.explain),
+        );
+        interpreter.lints = self.lints;
+        return interpreter;
+    }
+}
+
 impl Interpreter {
     pub fn new() -> Self {
         return Self {
             specials: HashMap::new(),
             environment: Environment::new(HashMap::new()),
+            lints: Lints::default(),
         };
     }
 
+    pub fn with_output(output: Output) -> Self {
+        return Self::builder().output(output).build();
+    }
+
+    pub fn builder() -> InterpreterBuilder {
+        return InterpreterBuilder::default();
+    }
+
+    /// Lexes, parses, resolves and interprets `src` in this interpreter.
+    /// Fails without running anything when a diagnostic is denied.
+    pub fn run(&mut self, src: &str) -> Result<(), String> {
+        let program = Program::compile_in(src, &self.environment)?;
+
+        let diagnostics = self.lints.apply(program.diagnostics());
+        if diagnostics.has_errors() {
+            let errors: Vec<&str> = diagnostics
+                .errors()
+                .map(|error| error.message.as_str())
+                .collect();
+            return Err(errors.join("\n"));
+        }
+
+        return self.run_program(&program);
+    }
+
+    pub fn lints(&self) -> &Lints {
+        return &self.lints;
+    }
+
+    /// Interprets an already compiled program.
+    pub fn run_program(&mut self, program: &Program) -> Result<(), String> {
+        self.resolve(program.locals().clone());
+        return self.interpret(program.stmts());
+    }
+
+    /// Runs `program` one statement at a time, for debuggers and
+    /// visualizations. Nothing runs until the first step.
+    pub fn stepper<'a>(&'a mut self, program: &'a Program) -> Stepper<'a> {
+        return Stepper::new(self, program);
+    }
+
+    /// Runs `src` in the current environment and returns the value of its
+    /// last statement when that is an expression, `nil` otherwise. The
+    /// semicolon after a trailing expression is optional.
+    pub fn eval(&mut self, src: &str) -> Result<Literal, String> {
+        let src = src.trim_end();
+        let program = if src.is_empty() || src.ends_with([';', '}']) {
+            Program::compile_in(src, &self.environment)?
+        } else {
+            Program::compile_in(&format!("{src};"), &self.environment)?
+        };
+        self.resolve(program.locals().clone());
+
+        let Some((last, rest)) = program.stmts().split_last() else {
+            return Ok(Literal::Nil);
+        };
+        self.interpret(rest)?;
+
+        if let Stmt::Expression { expression } = last {
+            return expression.evaluate(self.environment.clone());
+        }
+
+        self.execute(last)?;
+        return Ok(Literal::Nil);
+    }
+
+    /// Makes a host function callable from scripts under `name`.
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&[Literal]) -> Result<Literal, String> + MaybeSendSync + 'static,
+    ) {
+        let native = NativeFunctionImpl::new(name, arity, fun);
+        self.environment.define(
+            name.to_string(),
+            Literal::Callable(CallableImpl::NativeFunction(native)),
+        );
+    }
+
+    /// Makes `natives` importable from scripts as the module `name`.
+    pub fn register_module(&mut self, name: &str, natives: Vec<NativeFunctionImpl>) {
+        let members = natives
+            .into_iter()
+            .map(|native| {
+                let name = native.name.clone();
+                return (
+                    name,
+                    Literal::Callable(CallableImpl::NativeFunction(native)),
+                );
+            })
+            .collect();
+
+        self.environment.modules().register(name, members);
+    }
+
     pub fn resolve(&mut self, locals: HashMap<usize, usize>) {
         self.environment.resolve(locals);
     }
@@ -35,6 +224,7 @@ impl Interpreter {
         return Self {
             specials: HashMap::new(),
             environment: env,
+            lints: Lints::default(),
         };
     }
 
@@ -44,143 +234,303 @@ impl Interpreter {
         return Self {
             specials: HashMap::new(),
             environment: env,
+            lints: Lints::default(),
         };
     }
 
-    pub fn interpret(&mut self, stmts: Vec<&Stmt>) -> Result<(), String> {
+    pub fn interpret(&mut self, stmts: &[Stmt]) -> Result<(), String> {
         for stmt in stmts {
-            match stmt {
-                Stmt::Expression { expression } => {
-                    expression.evaluate(self.environment.clone())?;
-                }
-                Stmt::Print {
-                    expression,
-                    arguments,
-                } => {
-                    let value = expression.evaluate(self.environment.clone())?;
-                    let mut string = value.to_string();
-
-                    let mut args = Vec::new();
-                    for arg in arguments.iter().rev() {
-                        args.push(arg.evaluate(self.environment.clone())?);
-                    }
+            self.execute(stmt)?;
+        }
 
-                    while !args.is_empty() {
-                        let arg = args.pop().unwrap();
-                        string = string.replacen("{}", &arg.to_string(), 1);
-                    }
+        return Ok(());
+    }
+
+    /// Whether a `return`, `break` or `continue` skips the rest of the
+    /// enclosing statements.
+    fn is_jumping(&self) -> bool {
+        return ["return", "break", "continue"]
+            .iter()
+            .any(|special| self.specials.contains_key(*special));
+    }
+
+    /// Evaluates the expressions of a statement. In explain mode it also
+    /// prints how they reduce to their values, between `prefix` and `suffix`.
+    fn evaluate_all(
+        &self,
+        prefix: &str,
+        exprs: &[&Expr],
+        suffix: &str,
+    ) -> Result<Vec<Literal>, String> {
+        let evaluate = || {
+            return exprs
+                .iter()
+                .map(|expr| expr.evaluate(self.environment.clone()))
+                .collect::<Result<Vec<Literal>, String>>();
+        };
+
+        let Some(explain) = self.environment.explain() else {
+            return evaluate();
+        };
 
-                    println!("{}", string);
+        explain.begin();
+        let values = evaluate();
+        let line = explain.end(prefix, exprs, suffix);
+
+        if let (Ok(_), Some(line)) = (&values, line) {
+            self.environment.output().write_line(&line);
+        }
+        return values;
+    }
+
+    fn evaluate(&self, prefix: &str, expr: &Expr, suffix: &str) -> Result<Literal, String> {
+        let mut values = self.evaluate_all(prefix, &[expr], suffix)?;
+        return Ok(values.remove(0));
+    }
+
+    pub fn execute(&mut self, stmt: &Stmt) -> Result<(), String> {
+        match stmt {
+            Stmt::Expression { expression } => {
+                self.evaluate("", expression, "")?;
+            }
+            Stmt::Print {
+                expression,
+                arguments,
+            } => {
+                let exprs: Vec<&Expr> = std::iter::once(expression).chain(arguments).collect();
+                let values = self.evaluate_all("print(", &exprs, ")")?;
+
+                let string = natives::format(&values[0].to_string(), &values[1..]);
+                self.environment.output().write_line(&string);
+            }
+            Stmt::Var { name, initializer } => {
+                let value = self.evaluate(&format!("var {} = ", name.name), initializer, "")?;
+                explain::enter(&self.environment, &name.name, &value);
+                self.environment.define(name.name.clone(), value);
+            }
+            Stmt::Block { statements } => {
+                let new_environment = self.environment.enclose();
+                let old_environment = self.environment.clone();
+
+                self.environment = new_environment;
+                let mut block_result = Ok(());
+                for statement in statements {
+                    block_result = self.execute(statement);
+                    if block_result.is_err() || self.is_jumping() {
+                        break;
+                    }
                 }
-                Stmt::Var { name, initializer } => {
-                    let value = initializer.evaluate(self.environment.clone())?;
-                    self.environment.define(name.name.clone(), value);
+                if block_result.is_ok() {
+                    explain::leave(&self.environment);
                 }
-                Stmt::Block { statements } => {
-                    let new_environment = self.environment.enclose();
-                    let old_environment = self.environment.clone();
+                self.environment = old_environment;
 
-                    self.environment = new_environment;
-                    let block_result =
-                        self.interpret((*statements).iter().map(|b| b.as_ref()).collect());
-                    self.environment = old_environment;
+                block_result?;
+            }
+            Stmt::IfStmt {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let truth_value = self.evaluate("if (", condition, ")")?;
 
-                    block_result?;
+                if truth_value.is_truthy() == Literal::True {
+                    self.execute(then_branch)?;
+                } else if let Some(else_stmt) = else_branch {
+                    self.execute(else_stmt)?;
                 }
-                Stmt::IfStmt {
-                    condition,
-                    then_branch,
-                    else_branch,
-                } => {
-                    let truth_value = condition.evaluate(self.environment.clone())?;
-
-                    if truth_value.is_truthy() == Literal::True {
-                        self.interpret(vec![then_branch.as_ref()])?;
-                    } else if let Some(else_stmt) = else_branch {
-                        self.interpret(vec![else_stmt.as_ref()])?;
+            }
+            Stmt::WhileStmt {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
+                let mut flag = self.evaluate("while (", condition, ")")?;
+
+                while flag.is_truthy() == Literal::True {
+                    self.execute(body)?;
+
+                    if self.specials.remove("break").is_some()
+                        || self.specials.contains_key("return")
+                    {
+                        break;
                     }
-                }
-                Stmt::WhileStmt { condition, body } => {
-                    let mut flag = condition.evaluate(self.environment.clone())?;
+                    self.specials.remove("continue");
 
-                    while flag.is_truthy() == Literal::True {
-                        self.interpret(vec![body.as_ref()])?;
-                        flag = condition.evaluate(self.environment.clone())?;
+                    if let Some(increment) = increment {
+                        self.evaluate("", increment, "")?;
                     }
+                    flag = self.evaluate("while (", condition, ")")?;
                 }
-                Stmt::Function {
-                    name,
-                    params: _,
-                    body: _,
-                } => {
-                    let callable = self.make_function(stmt);
-                    let fun = Literal::Callable(CallableImpl::Function(callable));
-                    self.environment.define(name.name.clone(), fun);
-                }
-                Stmt::ReturnStmt { keyword: _, value } => {
-                    let eval_value = if let Some(value) = value {
-                        value.evaluate(self.environment.clone())?
+            }
+            Stmt::Break { .. } => {
+                self.specials.insert("break".to_string(), Literal::Nil);
+            }
+            Stmt::Continue { .. } => {
+                self.specials.insert("continue".to_string(), Literal::Nil);
+            }
+            Stmt::Function {
+                name,
+                params: _,
+                body: _,
+            } => {
+                let callable = self.make_function(stmt);
+                let fun = Literal::Callable(CallableImpl::Function(callable));
+                explain::enter(&self.environment, &name.name, &fun);
+                self.environment.define(name.name.clone(), fun);
+            }
+            Stmt::ReturnStmt { keyword: _, value } => {
+                let eval_value = if let Some(value) = value {
+                    self.evaluate("return ", value, "")?
+                } else {
+                    Literal::Nil
+                };
+
+                self.specials.insert("return".to_string(), eval_value);
+            }
+            Stmt::Class {
+                name,
+                methods,
+                static_methods,
+                superclass,
+            } => {
+                let mut methods_map = HashMap::new();
+                let mut static_methods_map = HashMap::new();
+
+                let superclass_value;
+                if let Some(superclass) = superclass {
+                    let superclass = superclass.evaluate(self.environment.clone())?;
+
+                    if let Literal::Class { .. } = superclass {
+                        superclass_value = Some(Box::new(superclass));
                     } else {
-                        Literal::Nil
-                    };
+                        return Err(format!(
+                            "Superclass must be a class, not '{}'",
+                            superclass.to_type(),
+                        ));
+                    }
+                } else {
+                    superclass_value = None;
+                }
 
-                    self.specials.insert("return".to_string(), eval_value);
+                self.environment.define(name.name.clone(), Literal::Nil);
+
+                self.environment = self.environment.enclose();
+                if let Some(sc) = superclass_value.clone() {
+                    self.environment.define("super".to_string(), *sc);
                 }
-                Stmt::Class {
-                    name,
-                    methods,
-                    superclass,
-                } => {
-                    let mut methods_map = HashMap::new();
-
-                    let superclass_value;
-                    if let Some(superclass) = superclass {
-                        let superclass = superclass.evaluate(self.environment.clone())?;
-
-                        if let Literal::Class { .. } = superclass {
-                            superclass_value = Some(Box::new(superclass));
-                        } else {
-                            return Err(format!(
-                                "Superclass must be a class, not '{}'",
-                                superclass.to_type(),
-                            ));
-                        }
+
+                for method in methods {
+                    if let Stmt::Function { name, .. } = method.as_ref() {
+                        let function = self.make_function(method.as_ref());
+                        methods_map.insert(name.name.clone(), function);
                     } else {
-                        superclass_value = None;
+                        panic!("Something that was not a function was in the methods of a class");
+                    }
+                }
+                for method in static_methods {
+                    if let Stmt::Function { name, .. } = method.as_ref() {
+                        let function = self.make_function(method.as_ref());
+                        static_methods_map.insert(name.name.clone(), function);
                     }
+                }
 
-                    self.environment.define(name.name.clone(), Literal::Nil);
+                let class = Literal::Class {
+                    name: name.name.clone(),
+                    methods: Shared::new(Lock::new(methods_map)),
+                    static_methods: Shared::new(Lock::new(static_methods_map)),
+                    superclass: superclass_value,
+                };
 
-                    self.environment = self.environment.enclose();
-                    if let Some(sc) = superclass_value.clone() {
-                        self.environment.define("super".to_string(), *sc);
-                    }
+                // Defined where it was declared as nil, which is not always
+                // the global scope
+                self.environment = *self.environment.enclosing.clone().unwrap();
+                self.environment.define(name.name.clone(), class);
+            }
+            Stmt::Extend {
+                class,
+                methods,
+                static_methods,
+            } => {
+                let class = class.evaluate(self.environment.clone())?;
+                let Literal::Class {
+                    methods: class_methods,
+                    static_methods: class_static_methods,
+                    superclass,
+                    ..
+                } = &class
+                else {
+                    return Err(format!(
+                        "Can only extend a class, not '{}'",
+                        class.to_type()
+                    ));
+                };
+
+                let old_environment = self.environment.clone();
+                self.environment = self.environment.enclose();
+                let superclass = superclass.as_deref().cloned().unwrap_or(Literal::Nil);
+                self.environment.define("super".to_string(), superclass);
 
-                    for method in methods {
-                        if let Stmt::Function { name, .. } = method.as_ref() {
-                            let function = self.make_function(method.as_ref());
-                            methods_map.insert(name.name.clone(), function);
-                        } else {
-                            panic!(
-                                "Something that was not a function was in the methods of a class"
-                            );
-                        }
+                for method in methods {
+                    if let Stmt::Function { name, .. } = method.as_ref() {
+                        let function = self.make_function(method.as_ref());
+                        class_methods
+                            .borrow_mut()
+                            .insert(name.name.clone(), function);
                     }
+                }
+                for method in static_methods {
+                    if let Stmt::Function { name, .. } = method.as_ref() {
+                        let function = self.make_function(method.as_ref());
+                        class_static_methods
+                            .borrow_mut()
+                            .insert(name.name.clone(), function);
+                    }
+                }
 
-                    let class = Literal::Class {
-                        name: name.name.clone(),
-                        methods: methods_map.clone(),
-                        superclass: superclass_value,
-                    };
+                self.environment = old_environment;
+            }
+            Stmt::Import { name } => {
+                let module = self
+                    .environment
+                    .modules()
+                    .import(&name.name, &self.environment)?;
+                self.environment.define(name.name.clone(), module);
+            }
+            Stmt::With {
+                name,
+                initializer,
+                body,
+                ..
+            } => {
+                let prefix = format!("with (var {} = ", name.name);
+                let resource = self.evaluate(&prefix, initializer, ")")?;
+                let close = resource.get_property("close").map_err(|_| {
+                    format!(
+                        "'with' expected a value with a close method but got {}",
+                        resource.to_type()
+                    )
+                })?;
 
-                    if !self.environment.assign_global(&name.name, class) {
-                        return Err(format!("Class definition failed for {}", name.name));
-                    }
+                let enclosing = self.environment.clone();
+                self.environment = enclosing.enclose();
+                explain::enter(&self.environment, &name.name, &resource);
+                self.environment.define(name.name.clone(), resource);
 
-                    self.environment = *self.environment.enclosing.clone().unwrap();
+                let result = self.execute(body);
+                if result.is_ok() {
+                    explain::leave(&self.environment);
                 }
-            };
-        }
+                self.environment = enclosing;
+
+                // An error of the body wins over one of `close`
+                let closed = close.call_in(vec![], &self.environment);
+                result?;
+                closed?;
+            }
+        };
 
         return Ok(());
     }
@@ -6,32 +6,40 @@ use crate::{
 };
 
 pub struct Lexer {
-    src: String,
+    src: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     curr: usize,
     line: usize,
+    // Index of the first character of the current line
+    line_start: usize,
+    // Column of `start`, from 1
+    start_column: usize,
 }
 
 impl Lexer {
     pub fn new(src: &str) -> Self {
         return Self {
-            src: src.to_string(),
+            src: src.chars().collect(),
             tokens: vec![],
             start: 0,
             curr: 0,
             line: 1,
+            line_start: 0,
+            start_column: 1,
         };
     }
 
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, String> {
         while !self.is_at_end() {
             self.start = self.curr;
+            self.start_column = self.curr - self.line_start + 1;
             self.scan_token()?;
         }
 
+        let column = self.curr - self.line_start + 1;
         self.tokens
-            .push(Token::new(TokenType::EOF, "", None, self.line));
+            .push(Token::new(TokenType::EOF, "", None, self.line, column));
 
         return Ok(self.tokens.clone());
     }
@@ -41,17 +49,18 @@ impl Lexer {
     }
 
     fn scan_token(&mut self) -> Result<(), String> {
-        let ch = self.advance();
-
-        if ch.is_none() {
-            return Err("No character found.".to_string());
-        }
+        let ch = match self.advance() {
+            Some(ch) => ch,
+            None => return Err("No character found.".to_string()),
+        };
 
-        match ch.unwrap() {
+        match ch {
             '(' => self.add_token(TokenType::LeftParen, None),
             ')' => self.add_token(TokenType::RightParen, None),
             '{' => self.add_token(TokenType::LeftBrace, None),
             '}' => self.add_token(TokenType::RightBrace, None),
+            '[' => self.add_token(TokenType::LeftBracket, None),
+            ']' => self.add_token(TokenType::RightBracket, None),
             ',' => self.add_token(TokenType::Comma, None),
             '.' => self.add_token(TokenType::Dot, None),
             '-' => self.add_token(TokenType::Minus, None),
@@ -73,19 +82,16 @@ impl Lexer {
                 }
             }
             '/' => self.add_token(TokenType::Slash, None),
-            '\n' => self.line += 1,
+            '\n' => {
+                self.line += 1;
+                self.line_start = self.curr;
+            }
             ' ' | '\r' | '\t' => {}
             '"' => self.string()?,
             '0'..='9' => self.number()?,
             'a'..='z' | 'A'..='Z' | '_' => self.identifier()?,
 
-            _ => {
-                return Err(format!(
-                    "Line {}: Unrecognized char {}",
-                    self.line,
-                    ch.unwrap()
-                ))
-            }
+            _ => return Err(format!("Line {}: Unrecognized char {}", self.line, ch)),
         }
 
         return Ok(());
@@ -98,9 +104,14 @@ impl Lexer {
     }
 
     fn add_token(&mut self, token_type: TokenType, literal: Option<Literal>) {
-        let text = self.src.get(self.start..self.curr).unwrap_or_default();
-        self.tokens
-            .push(Token::new(token_type, text, literal, self.line))
+        let text = self.text(self.start, self.curr);
+        self.tokens.push(Token::new(
+            token_type,
+            &text,
+            literal,
+            self.line,
+            self.start_column,
+        ))
     }
 
     fn char_match(&mut self, expected: char) -> bool {
@@ -117,11 +128,15 @@ impl Lexer {
     }
 
     fn peek(&self) -> Option<char> {
-        return self.src.chars().nth(self.curr);
+        return self.src.get(self.curr).copied();
     }
 
     fn peek_next(&self) -> Option<char> {
-        return self.src.chars().nth(self.curr + 1);
+        return self.src.get(self.curr + 1).copied();
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        return self.src[start..end].iter().collect();
     }
 
     fn is_alphanumeric(&self, ch: char) -> bool {
@@ -135,6 +150,7 @@ impl Lexer {
         while !self.is_at_end() && self.peek() != Some('"') {
             if self.peek() == Some('\n') {
                 self.line += 1;
+                self.line_start = self.curr + 1;
             }
 
             self.advance();
@@ -147,11 +163,8 @@ impl Lexer {
         // parse the closing "
         self.advance();
 
-        let value = self
-            .src
-            .get(self.start + 1..self.curr - 1)
-            .unwrap_or_default();
-        let literal = Literal::String(value.to_string());
+        let value = self.text(self.start + 1, self.curr - 1);
+        let literal = Literal::String(value);
 
         self.add_token(TokenType::String, Some(literal));
 
@@ -171,8 +184,24 @@ impl Lexer {
             }
         }
 
-        let value = self.src.get(self.start..self.curr).unwrap_or_default();
-        let literal = Literal::Number(value.parse().expect("Invalid number format."));
+        let value = self.text(self.start, self.curr);
+
+        // A `d` suffix makes a decimal literal, e.g. `10.50d`
+        if self.peek() == Some('d') && !self.is_alphanumeric(self.peek_next().unwrap_or_default()) {
+            self.advance();
+            let literal =
+                Literal::Decimal(value.parse().map_err(|_| {
+                    format!("Line {}: Invalid decimal literal {value}d", self.line)
+                })?);
+            self.add_token(TokenType::Decimal, Some(literal));
+            return Ok(());
+        }
+
+        let literal = Literal::Number(
+            value
+                .parse()
+                .map_err(|_| format!("Line {}: Invalid number {value}", self.line))?,
+        );
         self.add_token(TokenType::Number, Some(literal));
 
         return Ok(());
@@ -181,29 +210,37 @@ impl Lexer {
     fn identifier(&mut self) -> Result<(), String> {
         let keywords: HashMap<&str, TokenType> = HashMap::from([
             ("and", TokenType::And),
+            ("break", TokenType::Break),
             ("class", TokenType::Class),
+            ("continue", TokenType::Continue),
             ("else", TokenType::Else),
+            ("extend", TokenType::Extend),
             ("false", TokenType::False),
             ("for", TokenType::For),
             ("fun", TokenType::Fun),
             ("if", TokenType::If),
+            ("import", TokenType::Import),
             ("nil", TokenType::Nil),
             ("or", TokenType::Or),
             ("print", TokenType::Print),
             ("return", TokenType::Return),
+            ("static", TokenType::Static),
             ("super", TokenType::Super),
             ("this", TokenType::This),
             ("true", TokenType::True),
             ("var", TokenType::Var),
             ("while", TokenType::While),
+            ("with", TokenType::With),
         ]);
 
         while self.is_alphanumeric(self.peek().unwrap_or_default()) {
             self.advance();
         }
 
-        let value = self.src.get(self.start..self.curr).unwrap_or_default();
-        let token_type = keywords.get(value).unwrap_or(&TokenType::Identifier);
+        let value = self.text(self.start, self.curr);
+        let token_type = keywords
+            .get(value.as_str())
+            .unwrap_or(&TokenType::Identifier);
         self.add_token(*token_type, None);
         return Ok(());
     }
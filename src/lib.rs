@@ -1,9 +1,33 @@
+pub mod bundle;
+#[cfg(any(feature = "cdylib", feature = "plugins"))]
+mod c_value;
+pub mod diagnostics;
 pub mod environment;
+pub mod explain;
 pub mod expr;
+#[cfg(feature = "cdylib")]
+pub mod ffi;
 pub mod interpreter;
 pub mod lexer;
+pub mod module;
+pub mod natives;
+pub mod output;
 pub mod parser;
+pub mod permissions;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod program;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod resolver;
+pub mod semantic_tokens;
+pub mod server;
+pub mod source;
+pub mod stepper;
 pub mod stmt;
+pub mod symbols;
+pub mod sync;
 pub mod token;
 pub mod token_type;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
@@ -1,70 +1,262 @@
 use std::{
     env, fs,
     io::{self, Write},
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    path::Path,
     process::exit,
 };
 
 use programming_language::{
-    interpreter::Interpreter, lexer::Lexer, parser::Parser, resolver::Resolver,
+    bundle::bundle,
+    diagnostics::{Level, Lints},
+    interpreter::Interpreter,
+    output::Output,
+    permissions::Permissions,
+    program::Program,
+    semantic_tokens::classify_program,
+    server,
 };
 
+const USAGE: &str = "Usage: 'program_name' [--plugin path]... [--allow permission]... [-A|-W|-D lint]... [--symbols] [--tokens] [--strict] [--lox-compat] [--explain] [script]
+       'program_name' bundle script
+       'program_name' [options] [--remote] serve port|host:port|unix:path";
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = vec![];
+    let mut plugins: Vec<String> = vec![];
+    let mut permissions = Permissions::default();
+    let mut lints = Lints::default();
+    let mut symbols = false;
+    let mut tokens = false;
+    let mut strict = false;
+    let mut lox_compat = false;
+    let mut explain = false;
+    let mut remote = false;
+
+    let mut raw_args = env::args();
+    while let Some(arg) = raw_args.next() {
+        if arg == "--plugin" {
+            match raw_args.next() {
+                Some(path) => plugins.push(path),
+                None => error(USAGE, 64),
+            }
+        } else if arg == "--allow" {
+            let allowed = match raw_args.next() {
+                Some(name) => permissions.allow(&name),
+                None => Err(USAGE.to_string()),
+            };
+            if let Err(err) = allowed {
+                error(&err, 64);
+            }
+        } else if arg == "--symbols" {
+            symbols = true;
+        } else if arg == "--tokens" {
+            tokens = true;
+        } else if arg == "--strict" {
+            strict = true;
+        } else if arg == "--lox-compat" {
+            lox_compat = true;
+        } else if arg == "--explain" {
+            explain = true;
+        } else if arg == "--remote" {
+            remote = true;
+        } else if let Some(level) = lint_level(&arg) {
+            let set = match raw_args.next() {
+                Some(name) => lints.set_by_name(&name, level),
+                None => Err(USAGE.to_string()),
+            };
+            if let Err(err) = set {
+                error(&err, 64);
+            }
+        } else {
+            args.push(arg);
+        }
+    }
+
+    if args.get(1).is_some_and(|arg| arg == "bundle") {
+        let script = match &args[2..] {
+            [path] => bundle(Path::new(path)),
+            _ => Err(USAGE.to_string()),
+        };
+        match script {
+            Ok(script) => {
+                print!("{script}");
+                exit(0);
+            }
+            Err(err) => error(&err, 64),
+        }
+    }
+
+    let serving = args.get(1).is_some_and(|arg| arg == "serve");
+    let output = match serving {
+        true => Output::buffer(),
+        false => Output::Stdout,
+    };
+
+    let mut interpreter = Interpreter::builder()
+        .output(output)
+        .permissions(permissions)
+        .lints(lints)
+        .strict(strict)
+        .lox_compat(lox_compat)
+        .explain(explain)
+        .build();
+    for path in &plugins {
+        if let Err(err) = load_plugin(&mut interpreter, path) {
+            error(&err, 64);
+        }
+    }
+
+    if serving {
+        let served = match &args[2..] {
+            [address] => serve(&mut interpreter, address, remote),
+            _ => Err(USAGE.to_string()),
+        };
+        match served {
+            Ok(_) => exit(0),
+            Err(err) => error(&err, 64),
+        }
+    }
+
+    if symbols || tokens {
+        let src = match args.len() {
+            3 => Ok(args[2].clone()),
+            2 => fs::read_to_string(&args[1]).map_err(|err| err.to_string()),
+            _ => Err(USAGE.to_string()),
+        };
+        let printed = src.and_then(|src| match symbols {
+            true => print_symbols(&src, &interpreter),
+            false => print_tokens(&src, &interpreter),
+        });
+        match printed {
+            Ok(_) => exit(0),
+            Err(err) => error(&err, 64),
+        }
+    }
 
     match args.len() {
-        3 => match run_string(&args[2]) {
+        3 => match run_string(&args[2], &mut interpreter) {
             Ok(_) => exit(0),
             Err(err) => error(&err, 64),
         },
         2 => {
-            match run_file(&args[1]) {
+            match run_file(&args[1], &mut interpreter) {
                 Ok(_) => exit(0),
                 Err(err) => error(&err, 64),
             };
         }
-        1 => match run_prompt() {
+        1 => match run_prompt(&mut interpreter) {
             Ok(_) => exit(0),
             Err(err) => error(&err, 64),
         },
         _ => {
-            error("Usage: 'program_name' [script]", 64);
+            error(USAGE, 64);
         }
     };
 }
 
+fn lint_level(flag: &str) -> Option<Level> {
+    return match flag {
+        "-A" => Some(Level::Allow),
+        "-W" => Some(Level::Warn),
+        "-D" => Some(Level::Deny),
+        _ => None,
+    };
+}
+
+#[cfg(feature = "plugins")]
+fn load_plugin(interpreter: &mut Interpreter, path: &str) -> Result<(), String> {
+    return programming_language::plugin::load_plugin(interpreter, std::path::Path::new(path));
+}
+
+#[cfg(not(feature = "plugins"))]
+fn load_plugin(_interpreter: &mut Interpreter, path: &str) -> Result<(), String> {
+    return Err(format!(
+        "Cannot load plugin '{path}': built without the 'plugins' feature"
+    ));
+}
+
 pub fn error(message: &str, code: i32) {
     println!("Error: {message}");
     exit(code);
 }
 
-fn run_file(path: &str) -> Result<(), String> {
-    let mut interpreter = Interpreter::new();
+fn run_file(path: &str, interpreter: &mut Interpreter) -> Result<(), String> {
+    if let Some(dir) = Path::new(path).parent() {
+        interpreter
+            .environment
+            .modules()
+            .set_base_dir(dir.to_path_buf());
+    }
 
     return match fs::read_to_string(path) {
-        Ok(data) => run(&data, &mut interpreter),
+        Ok(data) => run_string(&data, interpreter),
         Err(err) => Err(err.to_string()),
     };
 }
 
-fn run(src: &str, interpreter: &mut Interpreter) -> Result<(), String> {
-    let mut lexer = Lexer::new(src);
-    let tokens = lexer.scan_tokens()?;
+/// Serves on the unix socket at the path after `unix:`, or else on the TCP
+/// `address`, on localhost when it is only a port. Anyone who can connect
+/// runs code, so addresses other than localhost need `remote`.
+fn serve(interpreter: &mut Interpreter, address: &str, remote: bool) -> Result<(), String> {
+    if let Some(path) = address.strip_prefix("unix:") {
+        return serve_unix(interpreter, path);
+    }
 
-    let mut parser = Parser::new(tokens);
-    let stmts = parser.parse()?;
+    let address = match address.parse::<u16>() {
+        Ok(port) => format!("127.0.0.1:{port}"),
+        Err(_) => address.to_string(),
+    };
 
-    let resolver = Resolver::new();
-    let locals = resolver.resolve(&stmts.iter().collect())?;
+    // Checked before binding, so that nothing is ever reachable without it
+    let socket_addrs: Vec<SocketAddr> = address
+        .to_socket_addrs()
+        .map_err(|err| format!("Cannot listen on '{address}': {err}"))?
+        .collect();
+    if !remote && !socket_addrs.iter().all(|addr| addr.ip().is_loopback()) {
+        return Err(format!(
+            "Not listening on '{address}', which is reachable from other machines, without --remote"
+        ));
+    }
 
-    interpreter.resolve(locals);
-    interpreter.interpret(stmts.iter().collect())?;
+    let listener = TcpListener::bind(&socket_addrs[..])
+        .map_err(|err| format!("Cannot listen on '{address}': {err}"))?;
+    eprintln!("Listening on {address}");
+    server::serve(interpreter, listener.incoming());
+    return Ok(());
+}
 
+#[cfg(unix)]
+fn serve_unix(interpreter: &mut Interpreter, path: &str) -> Result<(), String> {
+    let listener = std::os::unix::net::UnixListener::bind(path)
+        .map_err(|err| format!("Cannot listen on '{path}': {err}"))?;
+    eprintln!("Listening on {path}");
+    server::serve(interpreter, listener.incoming());
     return Ok(());
 }
 
-fn run_prompt() -> Result<(), String> {
-    let mut interpreter = Interpreter::new();
+#[cfg(not(unix))]
+fn serve_unix(_interpreter: &mut Interpreter, path: &str) -> Result<(), String> {
+    return Err(format!(
+        "Cannot listen on '{path}': unix sockets are not supported on this platform"
+    ));
+}
+
+fn print_symbols(src: &str, interpreter: &Interpreter) -> Result<(), String> {
+    let program = Program::compile_in(src, &interpreter.environment)?;
+    print!("{}", program.symbols());
+    return Ok(());
+}
+
+fn print_tokens(src: &str, interpreter: &Interpreter) -> Result<(), String> {
+    let program = Program::compile_in(src, &interpreter.environment)?;
+    for token in classify_program(src, &program)? {
+        println!("{token}");
+    }
+    return Ok(());
+}
 
+fn run_prompt(interpreter: &mut Interpreter) -> Result<(), String> {
     loop {
         print!("> ");
         io::stdout().flush().expect("Error while flushing.");
@@ -75,7 +267,7 @@ fn run_prompt() -> Result<(), String> {
         if buf.len() <= 2 {
             return Ok(());
         } else {
-            match run(&buf, &mut interpreter) {
+            match run_string(&buf, interpreter) {
                 Ok(_) => (),
                 Err(msg) => println!("{msg}"),
             };
@@ -85,7 +277,16 @@ fn run_prompt() -> Result<(), String> {
     }
 }
 
-pub fn run_string(contents: &str) -> Result<(), String> {
-    let mut interpreter = Interpreter::new();
-    return run(contents, &mut interpreter);
+pub fn run_string(contents: &str, interpreter: &mut Interpreter) -> Result<(), String> {
+    let program = Program::compile_in(contents, &interpreter.environment)?;
+
+    let diagnostics = interpreter.lints().apply(program.diagnostics());
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{diagnostic}");
+    }
+    if diagnostics.has_errors() {
+        return Err("Not running the program because of denied warnings".to_string());
+    }
+
+    return interpreter.run_program(&program);
 }
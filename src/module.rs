@@ -0,0 +1,107 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use crate::{
+    environment::Environment,
+    expr::Literal,
+    interpreter::Interpreter,
+    sync::{Lock, Shared},
+};
+
+pub const SCRIPT_EXTENSION: &str = "lox";
+
+#[derive(Debug, Default)]
+struct Registry {
+    loaded: HashMap<String, Literal>,
+    loading: HashSet<String>,
+    base_dir: PathBuf,
+}
+
+/// Modules available to `import`, shared by every environment of an
+/// interpreter.
+///
+/// A module is either registered by the host or loaded from the script file
+/// `<name>.lox` next to the main script. Scripts run in their own global
+/// scope and are only loaded once.
+#[derive(Debug, Clone, Default)]
+pub struct Modules {
+    registry: Shared<Lock<Registry>>,
+}
+
+impl Modules {
+    pub fn register(&self, name: &str, members: HashMap<String, Literal>) {
+        let module = Literal::Module {
+            name: name.to_string(),
+            members: Shared::new(members),
+        };
+
+        self.registry
+            .borrow_mut()
+            .loaded
+            .insert(name.to_string(), module);
+    }
+
+    pub fn set_base_dir(&self, dir: PathBuf) {
+        self.registry.borrow_mut().base_dir = dir;
+    }
+
+    pub fn path_of(&self, name: &str) -> PathBuf {
+        return self
+            .registry
+            .borrow()
+            .base_dir
+            .join(format!("{name}.{SCRIPT_EXTENSION}"));
+    }
+
+    pub fn import(&self, name: &str, environment: &Environment) -> Result<Literal, String> {
+        if let Some(module) = self.registry.borrow().loaded.get(name) {
+            return Ok(module.clone());
+        }
+
+        if !self.registry.borrow_mut().loading.insert(name.to_string()) {
+            return Err(format!("Circular import of module '{name}'"));
+        }
+
+        let module = self.load_script(name, environment);
+        self.registry.borrow_mut().loading.remove(name);
+
+        let module = module?;
+        self.registry
+            .borrow_mut()
+            .loaded
+            .insert(name.to_string(), module.clone());
+
+        return Ok(module);
+    }
+
+    fn load_script(&self, name: &str, environment: &Environment) -> Result<Literal, String> {
+        let path = self.path_of(name);
+        let src = fs::read_to_string(&path)
+            .map_err(|_| format!("Module '{name}' not found at '{}'", path.display()))?;
+
+        let module_env = environment.new_global();
+        let builtins = module_env.names();
+
+        let mut interpreter = Interpreter::with_env(module_env.clone());
+        interpreter
+            .run(&src)
+            .map_err(|err| format!("In module '{name}': {err}"))?;
+
+        let members = module_env
+            .names()
+            .difference(&builtins)
+            .filter_map(|member| {
+                let value = module_env.get_global(member)?;
+                return Some((member.clone(), value));
+            })
+            .collect();
+
+        return Ok(Literal::Module {
+            name: name.to_string(),
+            members: Shared::new(members),
+        });
+    }
+}
@@ -0,0 +1,133 @@
+//! CSV parsing and writing. Quoted fields may contain commas, newlines and
+//! quotes written as `""`. Every parsed field is a String, and `nil` is
+//! written as an empty field.
+
+use std::collections::HashMap;
+
+use super::{arg, define, define_optional, new_array, string_arg};
+use crate::expr::Literal;
+use crate::sync::{Lock, Shared};
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define_optional(env, "csv_parse", 1, Some(2), |args| {
+        let text = string_arg("csv_parse", args, 0)?;
+        let with_header = args
+            .get(1)
+            .is_some_and(|arg| arg.is_truthy() == Literal::True);
+
+        let mut rows = parse(text)?.into_iter();
+        if !with_header {
+            let rows = rows.map(|row| new_array(strings(row))).collect();
+            return Ok(new_array(rows));
+        }
+
+        let header = rows.next().unwrap_or_default();
+        let records = rows
+            .map(|row| {
+                let entries = header
+                    .iter()
+                    .zip(row.into_iter().chain(std::iter::repeat(String::new())))
+                    .map(|(key, value)| (Literal::String(key.clone()), Literal::String(value)))
+                    .collect();
+                return Literal::Map(Shared::new(Lock::new(entries)));
+            })
+            .collect();
+        return Ok(new_array(records));
+    });
+    define(env, "csv_stringify", 1, |args| {
+        let Literal::Array(rows) = arg("csv_stringify", args, 0)? else {
+            return Err(format!(
+                "csv_stringify expected an Array as argument 1 but got {}",
+                args[0].to_type()
+            ));
+        };
+
+        let rows = rows.borrow();
+        let mut lines = vec![];
+
+        if let Some(Literal::Map(first)) = rows.first() {
+            let header: Vec<Literal> = first.borrow().iter().map(|(key, _)| key.clone()).collect();
+            lines.push(write_row(&header));
+
+            for row in rows.iter() {
+                let values: Vec<Literal> = header
+                    .iter()
+                    .map(|key| row.map_get(key).unwrap_or(Literal::String(String::new())))
+                    .collect();
+                lines.push(write_row(&values));
+            }
+        } else {
+            for row in rows.iter() {
+                match row {
+                    Literal::Array(values) => lines.push(write_row(&values.borrow())),
+                    other => {
+                        return Err(format!(
+                            "csv_stringify expected rows to be Arrays or Maps but got {}",
+                            other.to_type()
+                        ))
+                    }
+                }
+            }
+        }
+
+        return Ok(Literal::String(lines.join("\n")));
+    });
+}
+
+fn strings(row: Vec<String>) -> Vec<Literal> {
+    return row.into_iter().map(Literal::String).collect();
+}
+
+fn parse(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match (ch, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => (),
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (ch, _) => field.push(ch),
+        }
+    }
+
+    if in_quotes {
+        return Err("csv_parse found an unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    return Ok(rows);
+}
+
+fn write_row(values: &[Literal]) -> String {
+    let fields: Vec<String> = values
+        .iter()
+        .map(|value| {
+            let value = match value {
+                Literal::Nil => String::new(),
+                other => other.to_string(),
+            };
+            if value.contains([',', '"', '\n', '\r']) {
+                return format!("\"{}\"", value.replace('"', "\"\""));
+            }
+            return value;
+        })
+        .collect();
+
+    return fields.join(",");
+}
@@ -0,0 +1,25 @@
+//! Construction of exact decimal numbers, see `Literal::Decimal`.
+
+use std::{collections::HashMap, str::FromStr};
+
+use rust_decimal::Decimal;
+
+use super::{arg, define};
+use crate::expr::{to_decimal, Literal};
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define(env, "decimal", 1, |args| {
+        return match arg("decimal", args, 0)? {
+            Literal::Decimal(x) => Ok(Literal::Decimal(*x)),
+            Literal::Number(x) => Ok(Literal::Decimal(to_decimal(*x)?)),
+            Literal::String(x) => match Decimal::from_str(x.trim()) {
+                Ok(x) => Ok(Literal::Decimal(x)),
+                Err(_) => Err(format!("Cannot convert \"{x}\" to a Decimal")),
+            },
+            other => Err(format!(
+                "decimal expected a Number or String as argument 1 but got {}",
+                other.to_type()
+            )),
+        };
+    });
+}
@@ -0,0 +1,31 @@
+//! Access to the process environment variables.
+
+use std::{collections::HashMap, env};
+
+use super::{define, string_arg};
+use crate::expr::Literal;
+use crate::permissions::Permissions;
+
+pub fn define_all(env: &mut HashMap<String, Literal>, permissions: Permissions) {
+    define(env, "env", 1, |args| {
+        let name = string_arg("env", args, 0)?;
+        return Ok(match env::var(name) {
+            Ok(value) => Literal::String(value),
+            Err(_) => Literal::Nil,
+        });
+    });
+    define(env, "set_env", 2, move |args| {
+        if !permissions.set_env {
+            return Err("set_env is not permitted, the host has to allow 'set_env'".to_string());
+        }
+
+        let name = string_arg("set_env", args, 0)?;
+        let value = string_arg("set_env", args, 1)?;
+        if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+            return Err(format!("Invalid environment variable '{name}'"));
+        }
+
+        env::set_var(name, value);
+        return Ok(Literal::Nil);
+    });
+}
@@ -0,0 +1,76 @@
+//! Path and directory functions for small file-handling scripts. Changing
+//! the file system needs the `fs_write` permission.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use super::{define, new_array, string_arg};
+use crate::expr::Literal;
+use crate::permissions::Permissions;
+
+pub fn define_all(env: &mut HashMap<String, Literal>, permissions: Permissions) {
+    define(env, "list_dir", 1, |args| {
+        let path = string_arg("list_dir", args, 0)?;
+        let entries = fs::read_dir(path).map_err(|err| format!("Cannot list '{path}': {err}"))?;
+
+        let mut names = entries
+            .map(|entry| {
+                let entry = entry.map_err(|err| format!("Cannot list '{path}': {err}"))?;
+                return Ok(entry.file_name().to_string_lossy().into_owned());
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        names.sort();
+
+        return Ok(new_array(names.into_iter().map(Literal::String).collect()));
+    });
+    define(env, "join_path", 2, |args| {
+        let base = string_arg("join_path", args, 0)?;
+        let child = string_arg("join_path", args, 1)?;
+        return Ok(path_literal(&Path::new(base).join(child)));
+    });
+    define(env, "basename", 1, |args| {
+        let path = Path::new(string_arg("basename", args, 0)?);
+        return Ok(match path.file_name() {
+            Some(name) => Literal::String(name.to_string_lossy().into_owned()),
+            None => Literal::String(String::new()),
+        });
+    });
+    define(env, "dirname", 1, |args| {
+        let path = Path::new(string_arg("dirname", args, 0)?);
+        return Ok(match path.parent() {
+            Some(parent) => path_literal(parent),
+            None => Literal::String(String::new()),
+        });
+    });
+    define(env, "mkdir", 1, move |args| {
+        check_fs_write("mkdir", permissions)?;
+        let path = string_arg("mkdir", args, 0)?;
+        fs::create_dir_all(path).map_err(|err| format!("Cannot create '{path}': {err}"))?;
+        return Ok(Literal::Nil);
+    });
+    define(env, "remove_dir", 1, move |args| {
+        check_fs_write("remove_dir", permissions)?;
+        let path = string_arg("remove_dir", args, 0)?;
+        fs::remove_dir(path).map_err(|err| format!("Cannot remove '{path}': {err}"))?;
+        return Ok(Literal::Nil);
+    });
+    define(env, "remove_file", 1, move |args| {
+        check_fs_write("remove_file", permissions)?;
+        let path = string_arg("remove_file", args, 0)?;
+        fs::remove_file(path).map_err(|err| format!("Cannot remove '{path}': {err}"))?;
+        return Ok(Literal::Nil);
+    });
+}
+
+fn check_fs_write(fun: &str, permissions: Permissions) -> Result<(), String> {
+    if !permissions.fs_write {
+        return Err(format!(
+            "{fun} is not permitted, the host has to allow 'fs_write'"
+        ));
+    }
+
+    return Ok(());
+}
+
+fn path_literal(path: &Path) -> Literal {
+    return Literal::String(path.to_string_lossy().into_owned());
+}
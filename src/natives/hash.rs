@@ -0,0 +1,40 @@
+//! Hashing of values and, with the `hashing` feature, hex digests of strings.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use super::{arg, define};
+use crate::expr::Literal;
+
+/// Keeps hashes exactly representable as a Number.
+const HASH_MASK: u64 = (1 << f64::MANTISSA_DIGITS) - 1;
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define(env, "hash", 1, |args| {
+        let mut hasher = DefaultHasher::new();
+        arg("hash", args, 0)?.hash(&mut hasher);
+        return Ok(Literal::Number((hasher.finish() & HASH_MASK) as f64));
+    });
+
+    #[cfg(feature = "hashing")]
+    {
+        use md5::Md5;
+        use sha2::{Digest, Sha256};
+
+        define(env, "sha256", 1, |args| {
+            let string = super::string_arg("sha256", args, 0)?;
+            return Ok(Literal::String(to_hex(&Sha256::digest(string))));
+        });
+        define(env, "md5", 1, |args| {
+            let string = super::string_arg("md5", args, 0)?;
+            return Ok(Literal::String(to_hex(&Md5::digest(string))));
+        });
+    }
+}
+
+#[cfg(feature = "hashing")]
+fn to_hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+}
@@ -0,0 +1,61 @@
+//! Functions on maps. Keys and values come back in insertion order.
+
+use std::collections::HashMap;
+
+use super::{arg, define, new_array};
+use crate::expr::Literal;
+use crate::sync::{Lock, Shared};
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define(env, "keys", 1, |args| {
+        let entries = map_arg("keys", args, 0)?;
+        let keys = entries.iter().map(|(key, _)| key.clone()).collect();
+        return Ok(new_array(keys));
+    });
+    define(env, "values", 1, |args| {
+        let entries = map_arg("values", args, 0)?;
+        let values = entries.iter().map(|(_, value)| value.clone()).collect();
+        return Ok(new_array(values));
+    });
+    define(env, "has", 2, |args| {
+        map_arg("has", args, 0)?;
+        let key = arg("has", args, 1)?;
+        return Ok(Literal::from_bool(args[0].map_get(key).is_some()));
+    });
+    define(env, "remove", 2, |args| {
+        map_arg("remove", args, 0)?;
+        let key = arg("remove", args, 1)?;
+        args[0].check_mutable()?;
+        let Literal::Map(entries) = &args[0] else {
+            unreachable!("checked by map_arg");
+        };
+
+        let mut entries = entries.borrow_mut();
+        return Ok(
+            match entries.iter().position(|(entry_key, _)| entry_key == key) {
+                Some(idx) => entries.remove(idx).1,
+                None => Literal::Nil,
+            },
+        );
+    });
+    define(env, "merge", 2, |args| {
+        let merged = Literal::Map(Shared::new(Lock::new(map_arg("merge", args, 0)?)));
+        for (key, value) in map_arg("merge", args, 1)? {
+            merged.map_insert(key, value);
+        }
+        return Ok(merged);
+    });
+}
+
+/// Returns a copy of the entries of the map at `idx`.
+fn map_arg(fun: &str, args: &[Literal], idx: usize) -> Result<Vec<(Literal, Literal)>, String> {
+    return match args.get(idx) {
+        Some(Literal::Map(entries)) => Ok(entries.borrow().clone()),
+        Some(other) => Err(format!(
+            "{fun} expected a Map as argument {} but got {}",
+            idx + 1,
+            other.to_type()
+        )),
+        None => Err(format!("{fun} expected at least {} arguments", idx + 1)),
+    };
+}
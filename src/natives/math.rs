@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::f64::consts::{E, PI};
+
+use super::{define, make_module, number_arg};
+use crate::expr::Literal;
+
+fn unary(members: &mut HashMap<String, Literal>, name: &'static str, fun: fn(f64) -> f64) {
+    define(members, name, 1, move |args| {
+        return Ok(Literal::Number(fun(number_arg(name, args, 0)?)));
+    });
+}
+
+fn binary(members: &mut HashMap<String, Literal>, name: &'static str, fun: fn(f64, f64) -> f64) {
+    define(members, name, 2, move |args| {
+        let x = number_arg(name, args, 0)?;
+        let y = number_arg(name, args, 1)?;
+        return Ok(Literal::Number(fun(x, y)));
+    });
+}
+
+/// The `math` module: `math.sqrt(2)`, `math.pi`, ...
+pub fn module() -> Literal {
+    let mut members = HashMap::new();
+
+    unary(&mut members, "abs", f64::abs);
+    unary(&mut members, "floor", f64::floor);
+    unary(&mut members, "ceil", f64::ceil);
+    unary(&mut members, "round", f64::round);
+    unary(&mut members, "sqrt", f64::sqrt);
+    unary(&mut members, "sin", f64::sin);
+    unary(&mut members, "cos", f64::cos);
+    unary(&mut members, "tan", f64::tan);
+    unary(&mut members, "log", f64::ln);
+    binary(&mut members, "pow", f64::powf);
+    binary(&mut members, "min", f64::min);
+    binary(&mut members, "max", f64::max);
+
+    members.insert("pi".to_string(), Literal::Number(PI));
+    members.insert("e".to_string(), Literal::Number(E));
+
+    return make_module("math", members);
+}
@@ -0,0 +1,372 @@
+//! Built-in methods of primitive values, e.g. `"abc".len()` or `(3.7).floor()`.
+
+use std::{cmp::Ordering, ops::RangeInclusive};
+
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+
+use super::{new_array, number_arg, string_arg};
+use crate::expr::{array_index, CallableImpl, Literal, NativeFunctionImpl};
+use crate::sync::{Lock, MaybeSendSync, Shared};
+
+type Array = Shared<Lock<Vec<Literal>>>;
+/// How many arguments a method takes, its optional ones last.
+type Arity = RangeInclusive<usize>;
+
+type StringMethod = fn(&str, &[Literal]) -> Result<Literal, String>;
+type NumberMethod = fn(f64, &[Literal]) -> Result<Literal, String>;
+type ArrayMethod = fn(&Array, &[Literal]) -> Result<Literal, String>;
+type DecimalMethod = fn(Decimal, &[Literal]) -> Result<Literal, String>;
+type BufferMethod = fn(&Shared<Lock<String>>, &[Literal]) -> Result<Literal, String>;
+
+fn string_method(name: &str) -> Option<(Arity, StringMethod)> {
+    let method: (Arity, StringMethod) = match name {
+        "len" => (0..=0, |this, _| {
+            Ok(Literal::Number(this.chars().count() as f64))
+        }),
+        "upper" => (0..=0, |this, _| Ok(Literal::String(this.to_uppercase()))),
+        "lower" => (0..=0, |this, _| Ok(Literal::String(this.to_lowercase()))),
+        "trim" => (0..=0, |this, _| {
+            Ok(Literal::String(this.trim().to_string()))
+        }),
+        "contains" => (1..=1, |this, args| {
+            let pattern = string_arg("contains", args, 0)?;
+            return Ok(Literal::from_bool(this.contains(pattern)));
+        }),
+        "starts_with" => (1..=1, |this, args| {
+            let prefix = string_arg("starts_with", args, 0)?;
+            return Ok(Literal::from_bool(this.starts_with(prefix)));
+        }),
+        "ends_with" => (1..=1, |this, args| {
+            let suffix = string_arg("ends_with", args, 0)?;
+            return Ok(Literal::from_bool(this.ends_with(suffix)));
+        }),
+        "replace" => (2..=2, |this, args| {
+            let from = string_arg("replace", args, 0)?;
+            let to = string_arg("replace", args, 1)?;
+            return Ok(Literal::String(this.replace(from, to)));
+        }),
+        "index_of" => (1..=1, |this, args| {
+            let pattern = string_arg("index_of", args, 0)?;
+            return Ok(match this.find(pattern) {
+                Some(idx) => Literal::Number(this[..idx].chars().count() as f64),
+                None => Literal::Number(-1.0),
+            });
+        }),
+        "split" => (1..=1, |this, args| {
+            let separator = string_arg("split", args, 0)?;
+            let parts = this
+                .split(separator)
+                .map(|part| Literal::String(part.to_string()))
+                .collect();
+            return Ok(new_array(parts));
+        }),
+        "substring" => (2..=2, |this, args| {
+            let start = number_arg("substring", args, 0)?.max(0.0) as usize;
+            let end = number_arg("substring", args, 1)?.max(0.0) as usize;
+            let substring = this.chars().take(end).skip(start).collect();
+            return Ok(Literal::String(substring));
+        }),
+        _ => return None,
+    };
+
+    return Some(method);
+}
+
+fn number_method(name: &str) -> Option<(Arity, NumberMethod)> {
+    let method: (Arity, NumberMethod) = match name {
+        "abs" => (0..=0, |this, _| Ok(Literal::Number(this.abs()))),
+        "floor" => (0..=0, |this, _| Ok(Literal::Number(this.floor()))),
+        "ceil" => (0..=0, |this, _| Ok(Literal::Number(this.ceil()))),
+        "round" => (0..=0, |this, _| Ok(Literal::Number(this.round()))),
+        "sqrt" => (0..=0, |this, _| Ok(Literal::Number(this.sqrt()))),
+        "to_string" => (0..=0, |this, _| Ok(Literal::String(this.to_string()))),
+        _ => return None,
+    };
+
+    return Some(method);
+}
+
+fn compare(a: &Literal, b: &Literal, comparator: Option<&Literal>) -> Result<Ordering, String> {
+    if let Some(comparator) = comparator {
+        return match comparator.call(vec![a.clone(), b.clone()])? {
+            Literal::Number(x) => Ok(x.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+            other => Err(format!(
+                "sort comparator must return a Number, not '{}'",
+                other.to_type()
+            )),
+        };
+    }
+
+    return match (a, b) {
+        (Literal::Number(x), Literal::Number(y)) => Ok(x.partial_cmp(y).unwrap_or(Ordering::Equal)),
+        (Literal::String(x), Literal::String(y)) => Ok(x.cmp(y)),
+        _ => Err(format!(
+            "Cannot compare '{}' and '{}' without a comparator",
+            a.to_type(),
+            b.to_type()
+        )),
+    };
+}
+
+/// Fails when the array was frozen by `freeze()`.
+fn check_mutable(array: &Array) -> Result<(), String> {
+    return Literal::Array(array.clone()).check_mutable();
+}
+
+/// Callbacks may modify the array, so they iterate over a copy.
+fn elements_of(array: &Array) -> Vec<Literal> {
+    return array.borrow().clone();
+}
+
+fn is_truthy(value: &Literal) -> bool {
+    return value.is_truthy() == Literal::True;
+}
+
+fn callback_arg<'a>(fun: &str, args: &'a [Literal]) -> Result<&'a Literal, String> {
+    return match args.first() {
+        Some(callback @ (Literal::Callable(_) | Literal::Class { .. })) => Ok(callback),
+        Some(other) => Err(format!(
+            "{fun} expected a function but got {}",
+            other.to_type()
+        )),
+        None => Err(format!("{fun} expected a function")),
+    };
+}
+
+fn array_method(name: &str) -> Option<(Arity, ArrayMethod)> {
+    let method: (Arity, ArrayMethod) = match name {
+        "len" => (0..=0, |this, _| {
+            Ok(Literal::Number(this.borrow().len() as f64))
+        }),
+        "push" => (1..=1, |this, args| {
+            check_mutable(this)?;
+            this.borrow_mut()
+                .push(args.first().cloned().unwrap_or(Literal::Nil));
+            return Ok(Literal::Nil);
+        }),
+        "pop" => (0..=0, |this, _| {
+            check_mutable(this)?;
+            return Ok(this.borrow_mut().pop().unwrap_or(Literal::Nil));
+        }),
+        "insert" => (2..=2, |this, args| {
+            check_mutable(this)?;
+            let mut elements = this.borrow_mut();
+            let idx = number_arg("insert", args, 0)?;
+            if idx.fract() != 0.0 || idx < 0.0 || idx as usize > elements.len() {
+                return Err(format!(
+                    "Index {idx} out of bounds for length {}",
+                    elements.len()
+                ));
+            }
+
+            elements.insert(idx as usize, args.get(1).cloned().unwrap_or(Literal::Nil));
+            return Ok(Literal::Nil);
+        }),
+        "remove" => (1..=1, |this, args| {
+            check_mutable(this)?;
+            let mut elements = this.borrow_mut();
+            let idx = array_index(args.first().unwrap_or(&Literal::Nil), elements.len())?;
+            return Ok(elements.remove(idx));
+        }),
+        "reverse" => (0..=0, |this, _| {
+            check_mutable(this)?;
+            this.borrow_mut().reverse();
+            return Ok(Literal::Nil);
+        }),
+        "sort" => (0..=1, |this, args| {
+            check_mutable(this)?;
+            let mut elements = elements_of(this);
+            let mut error = None;
+
+            elements.sort_by(|a, b| match compare(a, b, args.first()) {
+                Ok(ordering) => ordering,
+                Err(err) => {
+                    error.get_or_insert(err);
+                    Ordering::Equal
+                }
+            });
+
+            if let Some(err) = error {
+                return Err(err);
+            }
+
+            *this.borrow_mut() = elements;
+            return Ok(Literal::Nil);
+        }),
+        "slice" => (2..=2, |this, args| {
+            let elements = this.borrow();
+            let len = elements.len() as f64;
+            let start = number_arg("slice", args, 0)?.clamp(0.0, len) as usize;
+            let end = number_arg("slice", args, 1)?.clamp(0.0, len) as usize;
+
+            return Ok(new_array(elements[start..end.max(start)].to_vec()));
+        }),
+        "join" => (1..=1, |this, args| {
+            let separator = string_arg("join", args, 0)?;
+            let parts: Vec<String> = this.borrow().iter().map(|x| x.to_string()).collect();
+            return Ok(Literal::String(parts.join(separator)));
+        }),
+        "index_of" => (1..=1, |this, args| {
+            let value = args.first().unwrap_or(&Literal::Nil);
+            return Ok(match this.borrow().iter().position(|x| x == value) {
+                Some(idx) => Literal::Number(idx as f64),
+                None => Literal::Number(-1.0),
+            });
+        }),
+        "map" => (1..=1, |this, args| {
+            let fun = callback_arg("map", args)?;
+            let mut mapped = vec![];
+            for element in elements_of(this) {
+                mapped.push(fun.call(vec![element])?);
+            }
+
+            return Ok(new_array(mapped));
+        }),
+        "filter" => (1..=1, |this, args| {
+            let fun = callback_arg("filter", args)?;
+            let mut filtered = vec![];
+            for element in elements_of(this) {
+                if is_truthy(&fun.call(vec![element.clone()])?) {
+                    filtered.push(element);
+                }
+            }
+
+            return Ok(new_array(filtered));
+        }),
+        "reduce" => (1..=2, |this, args| {
+            let fun = callback_arg("reduce", args)?;
+            let mut elements = elements_of(this).into_iter();
+
+            let mut accumulator = match args.get(1) {
+                Some(initial) => initial.clone(),
+                None => elements
+                    .next()
+                    .ok_or("reduce of an empty array with no initial value")?,
+            };
+            for element in elements {
+                accumulator = fun.call(vec![accumulator, element])?;
+            }
+
+            return Ok(accumulator);
+        }),
+        "any" => (1..=1, |this, args| {
+            let fun = callback_arg("any", args)?;
+            for element in elements_of(this) {
+                if is_truthy(&fun.call(vec![element])?) {
+                    return Ok(Literal::True);
+                }
+            }
+
+            return Ok(Literal::False);
+        }),
+        "all" => (1..=1, |this, args| {
+            let fun = callback_arg("all", args)?;
+            for element in elements_of(this) {
+                if !is_truthy(&fun.call(vec![element])?) {
+                    return Ok(Literal::False);
+                }
+            }
+
+            return Ok(Literal::True);
+        }),
+        "find" => (1..=1, |this, args| {
+            let fun = callback_arg("find", args)?;
+            for element in elements_of(this) {
+                if is_truthy(&fun.call(vec![element.clone()])?) {
+                    return Ok(element);
+                }
+            }
+
+            return Ok(Literal::Nil);
+        }),
+        _ => return None,
+    };
+
+    return Some(method);
+}
+
+fn decimal_method(name: &str) -> Option<(Arity, DecimalMethod)> {
+    let method: (Arity, DecimalMethod) = match name {
+        "abs" => (0..=0, |this, _| Ok(Literal::Decimal(this.abs()))),
+        "round" => (0..=1, |this, args| {
+            let places = match args.first() {
+                Some(_) => number_arg("round", args, 0)?.max(0.0) as u32,
+                None => 0,
+            };
+            let rounded =
+                this.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero);
+            return Ok(Literal::Decimal(rounded));
+        }),
+        "to_number" => (0..=0, |this, _| {
+            Ok(Literal::Number(this.to_f64().unwrap_or(f64::NAN)))
+        }),
+        "to_string" => (0..=0, |this, _| Ok(Literal::String(this.to_string()))),
+        _ => return None,
+    };
+
+    return Some(method);
+}
+
+fn buffer_method(name: &str) -> Option<(Arity, BufferMethod)> {
+    let method: (Arity, BufferMethod) = match name {
+        "push" => (1..=1, |this, args| {
+            let value = args.first().unwrap_or(&Literal::Nil).to_string();
+            this.borrow_mut().push_str(&value);
+            return Ok(Literal::Nil);
+        }),
+        "len" => (0..=0, |this, _| {
+            Ok(Literal::Number(this.borrow().chars().count() as f64))
+        }),
+        "clear" => (0..=0, |this, _| {
+            this.borrow_mut().clear();
+            return Ok(Literal::Nil);
+        }),
+        "to_string" => (0..=0, |this, _| Ok(Literal::String(this.borrow().clone()))),
+        _ => return None,
+    };
+
+    return Some(method);
+}
+
+fn native(
+    name: &str,
+    arity: Arity,
+    fun: impl Fn(&[Literal]) -> Result<Literal, String> + MaybeSendSync + 'static,
+) -> NativeFunctionImpl {
+    return NativeFunctionImpl::new(name, *arity.start(), fun).with_max_arity(Some(*arity.end()));
+}
+
+/// Looks up the built-in method `name` of `receiver` and binds it to the
+/// receiver, or returns `None` if its type has no such method.
+pub fn get_method(receiver: &Literal, name: &str) -> Option<Literal> {
+    let native = match receiver {
+        Literal::String(this) => {
+            let (arity, method) = string_method(name)?;
+            let this = this.clone();
+            native(name, arity, move |args| method(&this, args))
+        }
+        Literal::Number(this) => {
+            let (arity, method) = number_method(name)?;
+            let this = *this;
+            native(name, arity, move |args| method(this, args))
+        }
+        Literal::Array(this) => {
+            let (arity, method) = array_method(name)?;
+            let this = this.clone();
+            native(name, arity, move |args| method(&this, args))
+        }
+        Literal::Decimal(this) => {
+            let (arity, method) = decimal_method(name)?;
+            let this = *this;
+            native(name, arity, move |args| method(this, args))
+        }
+        Literal::Buffer(this) => {
+            let (arity, method) = buffer_method(name)?;
+            let this = this.clone();
+            native(name, arity, move |args| method(&this, args))
+        }
+        _ => return None,
+    };
+
+    return Some(Literal::Callable(CallableImpl::NativeFunction(native)));
+}
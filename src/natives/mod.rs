@@ -0,0 +1,181 @@
+//! Functions and modules predefined in every global scope.
+
+mod csv;
+mod decimal;
+mod env;
+mod fs;
+mod hash;
+mod map;
+mod math;
+mod methods;
+mod process;
+#[cfg(feature = "regex")]
+mod regex;
+mod strings;
+mod time;
+mod values;
+
+pub use methods::get_method;
+pub use strings::format;
+
+use std::collections::HashMap;
+
+use crate::environment::Environment;
+use crate::expr::{CallableImpl, Literal, NativeFunctionImpl};
+use crate::interpreter::Interpreter;
+use crate::permissions::Permissions;
+use crate::sync::{Lock, MaybeSendSync, Shared};
+
+pub fn get_globals(permissions: Permissions) -> HashMap<String, Literal> {
+    let mut env = HashMap::new();
+
+    define(&mut env, "clock", 0, clock_impl);
+    define(&mut env, "error", 1, |args| {
+        return Err(arg("error", args, 0)?.to_string());
+    });
+    define(&mut env, "panic", 1, |args| {
+        return Err(format!("Panic: {}", arg("panic", args, 0)?));
+    });
+    define_with_env(&mut env, "eval", 1, move |args, environment| {
+        if !permissions.eval {
+            return Err("eval is not permitted, the host has to allow 'eval'".to_string());
+        }
+
+        let src = string_arg("eval", args, 0)?;
+        return Interpreter::with_env(environment.clone()).eval(src);
+    });
+    define_with_env(&mut env, "globals", 0, |_args, environment| {
+        return Ok(sorted_map(environment.user_globals()));
+    });
+    define_with_env(&mut env, "locals", 0, |_args, environment| {
+        return Ok(sorted_map(environment.local_values()));
+    });
+
+    env.insert("math".to_string(), math::module());
+    map::define_all(&mut env);
+    fs::define_all(&mut env, permissions);
+    env::define_all(&mut env, permissions);
+    time::define_all(&mut env);
+    strings::define_all(&mut env);
+    hash::define_all(&mut env);
+    csv::define_all(&mut env);
+    decimal::define_all(&mut env);
+    values::define_all(&mut env);
+    process::define_all(&mut env, permissions);
+    #[cfg(feature = "regex")]
+    regex::define_all(&mut env);
+
+    return env;
+}
+
+fn define(
+    env: &mut HashMap<String, Literal>,
+    name: &str,
+    arity: usize,
+    fun: impl Fn(&[Literal]) -> Result<Literal, String> + MaybeSendSync + 'static,
+) {
+    let callable_impl = NativeFunctionImpl::new(name, arity, fun);
+
+    env.insert(
+        name.to_string(),
+        Literal::Callable(CallableImpl::NativeFunction(callable_impl)),
+    );
+}
+
+/// Like [`define`] for a native whose arguments after the first `arity`
+/// are optional, up to `max_arity` or any number when `None`.
+fn define_optional(
+    env: &mut HashMap<String, Literal>,
+    name: &str,
+    arity: usize,
+    max_arity: Option<usize>,
+    fun: impl Fn(&[Literal]) -> Result<Literal, String> + MaybeSendSync + 'static,
+) {
+    let callable_impl = NativeFunctionImpl::new(name, arity, fun).with_max_arity(max_arity);
+
+    env.insert(
+        name.to_string(),
+        Literal::Callable(CallableImpl::NativeFunction(callable_impl)),
+    );
+}
+
+/// Defines a native that receives the environment it is called from.
+fn define_with_env(
+    env: &mut HashMap<String, Literal>,
+    name: &str,
+    arity: usize,
+    fun: impl Fn(&[Literal], &Environment) -> Result<Literal, String> + MaybeSendSync + 'static,
+) {
+    let callable_impl = NativeFunctionImpl::with_env(name, arity, fun);
+
+    env.insert(
+        name.to_string(),
+        Literal::Callable(CallableImpl::NativeFunction(callable_impl)),
+    );
+}
+
+fn make_module(name: &str, members: HashMap<String, Literal>) -> Literal {
+    return Literal::Module {
+        name: name.to_string(),
+        members: Shared::new(members),
+    };
+}
+
+/// A map of `values` with the names in alphabetical order.
+fn sorted_map(values: HashMap<String, Literal>) -> Literal {
+    let mut entries: Vec<(Literal, Literal)> = values
+        .into_iter()
+        .map(|(name, value)| (Literal::String(name), value))
+        .collect();
+    entries.sort_by_key(|(name, _)| name.to_string());
+    return Literal::Map(Shared::new(Lock::new(entries)));
+}
+
+fn new_array(elements: Vec<Literal>) -> Literal {
+    return Literal::Array(Shared::new(Lock::new(elements)));
+}
+
+fn arg<'a>(fun: &str, args: &'a [Literal], idx: usize) -> Result<&'a Literal, String> {
+    return args
+        .get(idx)
+        .ok_or_else(|| format!("{fun} expected at least {} arguments", idx + 1));
+}
+
+fn number_arg(fun: &str, args: &[Literal], idx: usize) -> Result<f64, String> {
+    return match args.get(idx) {
+        Some(Literal::Number(x)) => Ok(*x),
+        Some(other) => Err(format!(
+            "{fun} expected a Number as argument {} but got {}",
+            idx + 1,
+            other.to_type()
+        )),
+        None => Err(format!("{fun} expected at least {} arguments", idx + 1)),
+    };
+}
+
+fn string_arg<'a>(fun: &str, args: &'a [Literal], idx: usize) -> Result<&'a str, String> {
+    return match args.get(idx) {
+        Some(Literal::String(x)) => Ok(x),
+        Some(other) => Err(format!(
+            "{fun} expected a String as argument {} but got {}",
+            idx + 1,
+            other.to_type()
+        )),
+        None => Err(format!("{fun} expected at least {} arguments", idx + 1)),
+    };
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm-bindgen"))]
+fn clock_impl(_args: &[Literal]) -> Result<Literal, String> {
+    return Ok(Literal::Number(js_sys::Date::now() / 1000.0));
+}
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm-bindgen")))]
+fn clock_impl(_args: &[Literal]) -> Result<Literal, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .expect("Could not get system time")
+        .as_millis();
+
+    return Ok(Literal::Number(now as f64 / 1000.0));
+}
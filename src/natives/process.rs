@@ -0,0 +1,60 @@
+//! Running subprocesses, only available with the `exec` permission.
+
+use std::{collections::HashMap, process::Command};
+
+use super::{arg, define, string_arg};
+use crate::expr::Literal;
+use crate::permissions::Permissions;
+use crate::sync::{Lock, Shared};
+
+pub fn define_all(env: &mut HashMap<String, Literal>, permissions: Permissions) {
+    define(env, "exec", 2, move |args| {
+        if !permissions.exec {
+            return Err("exec is not permitted, the host has to allow 'exec'".to_string());
+        }
+
+        let command = string_arg("exec", args, 0)?;
+        let arguments = match arg("exec", args, 1)? {
+            Literal::Array(elements) => elements
+                .borrow()
+                .iter()
+                .map(|arg| match arg {
+                    Literal::String(x) => Ok(x.clone()),
+                    other => Err(format!(
+                        "exec expected String arguments but got {}",
+                        other.to_type()
+                    )),
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+            other => {
+                return Err(format!(
+                    "exec expected an Array as argument 2 but got {}",
+                    other.to_type()
+                ))
+            }
+        };
+
+        let output = Command::new(command)
+            .args(arguments)
+            .output()
+            .map_err(|err| format!("Cannot run '{command}': {err}"))?;
+
+        let status = match output.status.code() {
+            Some(code) => Literal::Number(code as f64),
+            None => Literal::Nil,
+        };
+        let result = vec![
+            (Literal::String("status".to_string()), status),
+            (
+                Literal::String("stdout".to_string()),
+                Literal::String(String::from_utf8_lossy(&output.stdout).into_owned()),
+            ),
+            (
+                Literal::String("stderr".to_string()),
+                Literal::String(String::from_utf8_lossy(&output.stderr).into_owned()),
+            ),
+        ];
+
+        return Ok(Literal::Map(Shared::new(Lock::new(result))));
+    });
+}
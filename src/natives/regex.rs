@@ -0,0 +1,38 @@
+//! Regular expressions, available with the `regex` feature.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use super::{define, new_array, string_arg};
+use crate::expr::Literal;
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define(env, "regex_match", 2, |args| {
+        let regex = regex_arg("regex_match", args, 0)?;
+        let string = string_arg("regex_match", args, 1)?;
+        return Ok(Literal::from_bool(regex.is_match(string)));
+    });
+    define(env, "regex_find_all", 2, |args| {
+        let regex = regex_arg("regex_find_all", args, 0)?;
+        let string = string_arg("regex_find_all", args, 1)?;
+        let matches = regex
+            .find_iter(string)
+            .map(|found| Literal::String(found.as_str().to_string()))
+            .collect();
+        return Ok(new_array(matches));
+    });
+    define(env, "regex_replace", 3, |args| {
+        let regex = regex_arg("regex_replace", args, 0)?;
+        let string = string_arg("regex_replace", args, 1)?;
+        let replacement = string_arg("regex_replace", args, 2)?;
+        return Ok(Literal::String(
+            regex.replace_all(string, replacement).into_owned(),
+        ));
+    });
+}
+
+fn regex_arg(fun: &str, args: &[Literal], idx: usize) -> Result<Regex, String> {
+    let pattern = string_arg(fun, args, idx)?;
+    return Regex::new(pattern).map_err(|err| format!("Invalid regex '{pattern}': {err}"));
+}
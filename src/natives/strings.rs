@@ -0,0 +1,63 @@
+//! Global functions working on strings.
+
+use std::collections::HashMap;
+
+use super::{arg, define, define_optional, number_arg, string_arg};
+use crate::expr::Literal;
+use crate::sync::{Lock, Shared};
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define_optional(env, "buffer", 0, Some(1), |args| {
+        let initial = match args.first() {
+            Some(value) => value.to_string(),
+            None => String::new(),
+        };
+        return Ok(Literal::Buffer(Shared::new(Lock::new(initial))));
+    });
+    define_optional(env, "format", 1, None, |args| {
+        let template = arg("format", args, 0)?.to_string();
+        return Ok(Literal::String(format(&template, &args[1..])));
+    });
+    define(env, "ord", 1, |args| {
+        let string = string_arg("ord", args, 0)?;
+        let mut chars = string.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(Literal::Number(ch as u32 as f64)),
+            _ => Err(format!(
+                "ord expected a single character but got \"{string}\""
+            )),
+        };
+    });
+    define(env, "chr", 1, |args| {
+        let code = number_arg("chr", args, 0)?;
+        let ch = if code.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&code) {
+            char::from_u32(code as u32)
+        } else {
+            None
+        };
+        return match ch {
+            Some(ch) => Ok(Literal::String(ch.to_string())),
+            None => Err(format!("chr got the invalid character code {code}")),
+        };
+    });
+}
+
+/// Replaces each `{}` in `template` with the next argument, as `print`
+/// does. Placeholders without an argument are kept.
+pub fn format(template: &str, args: &[Literal]) -> String {
+    let mut result = String::new();
+    let mut args = args.iter();
+    let mut parts = template.split("{}").peekable();
+
+    while let Some(part) = parts.next() {
+        result.push_str(part);
+        if parts.peek().is_some() {
+            match args.next() {
+                Some(arg) => result.push_str(&arg.to_string()),
+                None => result.push_str("{}"),
+            }
+        }
+    }
+
+    return result;
+}
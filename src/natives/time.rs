@@ -0,0 +1,93 @@
+//! Date and time functions. Dates are `DateTime` values in UTC, and every
+//! function taking a date also accepts a timestamp in seconds, as returned
+//! by `clock()`.
+
+use std::collections::HashMap;
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+
+use super::{define, string_arg};
+use crate::expr::Literal;
+
+type Component = fn(&DateTime<Utc>) -> f64;
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define(env, "now", 0, |_args| {
+        return Ok(Literal::DateTime(Utc::now()));
+    });
+    define(env, "format_time", 2, |args| {
+        let time = date_arg("format_time", args, 0)?;
+        let format = string_arg("format_time", args, 1)?;
+
+        let items: Vec<Item> = StrftimeItems::new(format).collect();
+        if items.contains(&Item::Error) {
+            return Err(format!("Invalid time format '{format}'"));
+        }
+
+        return Ok(Literal::String(
+            time.format_with_items(items.into_iter()).to_string(),
+        ));
+    });
+    define(env, "parse_time", 2, |args| {
+        let string = string_arg("parse_time", args, 0)?;
+        let format = string_arg("parse_time", args, 1)?;
+        return Ok(Literal::DateTime(parse(string, format)?));
+    });
+    define(env, "timestamp", 1, |args| {
+        let time = date_arg("timestamp", args, 0)?;
+        return Ok(Literal::Number(time.timestamp_millis() as f64 / 1000.0));
+    });
+
+    let components: [(&str, Component); 7] = [
+        ("year", |time| time.year() as f64),
+        ("month", |time| time.month() as f64),
+        ("day", |time| time.day() as f64),
+        ("hour", |time| time.hour() as f64),
+        ("minute", |time| time.minute() as f64),
+        ("second", |time| time.second() as f64),
+        ("weekday", |time| time.weekday().number_from_monday() as f64),
+    ];
+    for (name, component) in components {
+        define(env, name, 1, move |args| {
+            let time = date_arg(name, args, 0)?;
+            return Ok(Literal::Number(component(&time)));
+        });
+    }
+}
+
+/// Parses a full timestamp, falling back to a date at midnight when the
+/// format has no time of day.
+fn parse(string: &str, format: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_str(string, format) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(string, format) {
+        return Ok(time.and_utc());
+    }
+
+    return match NaiveDate::parse_from_str(string, format) {
+        Ok(date) => Ok(date.and_time(Default::default()).and_utc()),
+        Err(err) => Err(format!(
+            "Cannot parse '{string}' with format '{format}': {err}"
+        )),
+    };
+}
+
+fn date_arg(fun: &str, args: &[Literal], idx: usize) -> Result<DateTime<Utc>, String> {
+    return match args.get(idx) {
+        Some(Literal::DateTime(x)) => Ok(*x),
+        Some(Literal::Number(x)) => match Utc.timestamp_millis_opt((x * 1000.0) as i64) {
+            chrono::LocalResult::Single(time) => Ok(time),
+            _ => Err(format!("{fun} got the invalid timestamp {x}")),
+        },
+        Some(other) => Err(format!(
+            "{fun} expected a DateTime as argument {} but got {}",
+            idx + 1,
+            other.to_type()
+        )),
+        None => Err(format!("{fun} expected at least {} arguments", idx + 1)),
+    };
+}
@@ -0,0 +1,228 @@
+//! Functions working on values of any type.
+
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use super::{arg, define, string_arg};
+use crate::expr::{CallableImpl, Literal, NativeFunctionImpl};
+use crate::sync::{Lock, Shared};
+
+pub fn define_all(env: &mut HashMap<String, Literal>) {
+    define(env, "deep_equals", 2, |args| {
+        let a = arg("deep_equals", args, 0)?;
+        let b = arg("deep_equals", args, 1)?;
+        return Ok(Literal::from_bool(deep_equals(a, b, &mut vec![])));
+    });
+    define(env, "clone", 1, |args| {
+        let value = arg("clone", args, 0)?;
+        return Ok(deep_clone(value, &mut HashMap::new()));
+    });
+    define(env, "arity", 1, |args| {
+        let (_, arity) = signature("arity", arg("arity", args, 0)?)?;
+        return Ok(Literal::Number(arity as f64));
+    });
+    define(env, "fn_name", 1, |args| {
+        let (name, _) = signature("fn_name", arg("fn_name", args, 0)?)?;
+        return Ok(Literal::String(name));
+    });
+    define(env, "memoize", 1, |args| {
+        let fun = arg("memoize", args, 0)?.clone();
+        let (name, arity) = signature("memoize", &fun)?;
+        let max_arity = match &fun {
+            Literal::Callable(CallableImpl::NativeFunction(native)) => native.max_arity,
+            _ => Some(arity),
+        };
+        let cache: Shared<Lock<HashMap<Arguments, Literal>>> = Shared::default();
+
+        let memoized = NativeFunctionImpl::new(&name, arity, move |args| {
+            let key = Arguments(args.to_vec());
+            if let Some(result) = cache.borrow().get(&key) {
+                return Ok(result.clone());
+            }
+
+            // The lock is released while calling, as `fun` may recurse
+            let result = fun.call(args.to_vec())?;
+            cache.borrow_mut().insert(key, result.clone());
+            return Ok(result);
+        })
+        .with_max_arity(max_arity);
+        return Ok(Literal::Callable(CallableImpl::NativeFunction(memoized)));
+    });
+    define(env, "freeze", 1, |args| {
+        let value = arg("freeze", args, 0)?;
+        match value {
+            Literal::Array(elements) => elements.freeze(),
+            Literal::Map(entries) => entries.freeze(),
+            Literal::Instance { fields, .. } => fields.freeze(),
+            other => {
+                return Err(format!(
+                    "freeze expected an Array, Map or Instance but got {}",
+                    other.to_type()
+                ))
+            }
+        }
+        return Ok(value.clone());
+    });
+    define(env, "is_frozen", 1, |args| {
+        return Ok(Literal::from_bool(arg("is_frozen", args, 0)?.is_frozen()));
+    });
+    define(env, "get_field", 2, |args| {
+        let object = arg("get_field", args, 0)?;
+        let name = string_arg("get_field", args, 1)?;
+        return object.get_property(name);
+    });
+    define(env, "set_field", 3, |args| {
+        let object = arg("set_field", args, 0)?;
+        let name = string_arg("set_field", args, 1)?;
+        let value = arg("set_field", args, 2)?;
+        object.set_property(name, value.clone())?;
+        return Ok(Literal::Nil);
+    });
+}
+
+/// Cache key of a memoized function.
+#[derive(PartialEq)]
+struct Arguments(Vec<Literal>);
+
+impl Eq for Arguments {}
+
+impl Hash for Arguments {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+/// Name and number of parameters of anything callable. A class takes the
+/// parameters of its `init` method.
+fn signature(fun: &str, callable: &Literal) -> Result<(String, usize), String> {
+    return match callable {
+        Literal::Callable(CallableImpl::Function(f)) => Ok((f.name.clone(), f.arity)),
+        Literal::Callable(CallableImpl::NativeFunction(f)) => Ok((f.name.clone(), f.arity)),
+        Literal::Class { name, methods, .. } => {
+            let arity = methods.borrow().get("init").map_or(0, |init| init.arity);
+            Ok((name.clone(), arity))
+        }
+        other => Err(format!(
+            "{fun} expected a Callable as argument 1 but got {}",
+            other.to_type()
+        )),
+    };
+}
+
+/// Identity of the shared storage behind arrays, maps and instances.
+fn address(value: &Literal) -> Option<usize> {
+    return match value {
+        Literal::Array(x) => Some(Shared::as_ptr(x) as *const () as usize),
+        Literal::Map(x) => Some(Shared::as_ptr(x) as *const () as usize),
+        Literal::Instance { fields, .. } => Some(Shared::as_ptr(fields) as *const () as usize),
+        _ => None,
+    };
+}
+
+/// Compares the contents of arrays, maps and instances recursively. Pairs
+/// already being compared further up count as equal, so cycles terminate.
+fn deep_equals(a: &Literal, b: &Literal, comparing: &mut Vec<(usize, usize)>) -> bool {
+    if let (Some(x), Some(y)) = (address(a), address(b)) {
+        if x == y || comparing.contains(&(x, y)) {
+            return true;
+        }
+        comparing.push((x, y));
+    }
+
+    let equal = match (a, b) {
+        (Literal::Array(x), Literal::Array(y)) => {
+            let (x, y) = (x.borrow().clone(), y.borrow().clone());
+            x.len() == y.len()
+                && x.iter()
+                    .zip(y.iter())
+                    .all(|(x, y)| deep_equals(x, y, comparing))
+        }
+        (Literal::Map(x), Literal::Map(y)) => {
+            let (x, y) = (x.borrow().clone(), y.borrow().clone());
+            x.len() == y.len()
+                && x.iter().all(|(key, x)| {
+                    return match y.iter().find(|(other, _)| other == key) {
+                        Some((_, y)) => deep_equals(x, y, comparing),
+                        None => false,
+                    };
+                })
+        }
+        (
+            Literal::Instance { class, fields },
+            Literal::Instance {
+                class: other_class,
+                fields: other_fields,
+            },
+        ) => {
+            let (x, y) = (fields.borrow().clone(), other_fields.borrow().clone());
+            class.to_string() == other_class.to_string()
+                && x.len() == y.len()
+                && x.iter().all(|(name, x)| {
+                    return match y.iter().find(|(other, _)| other == name) {
+                        Some((_, y)) => deep_equals(x, y, comparing),
+                        None => false,
+                    };
+                })
+        }
+        (a, b) => a == b,
+    };
+
+    if address(a).is_some() && address(b).is_some() {
+        comparing.pop();
+    }
+    return equal;
+}
+
+/// Copies arrays, maps and instances recursively. Values shared several
+/// times, including cycles, are shared the same way in the copy.
+fn deep_clone(value: &Literal, copies: &mut HashMap<usize, Literal>) -> Literal {
+    let Some(address) = address(value) else {
+        return value.clone();
+    };
+    if let Some(copy) = copies.get(&address) {
+        return copy.clone();
+    }
+
+    return match value {
+        Literal::Array(elements) => {
+            let copy = Shared::new(Lock::new(vec![]));
+            copies.insert(address, Literal::Array(copy.clone()));
+
+            let elements = elements.borrow().clone();
+            let elements = elements.iter().map(|x| deep_clone(x, copies)).collect();
+            *copy.borrow_mut() = elements;
+            Literal::Array(copy)
+        }
+        Literal::Map(entries) => {
+            let copy = Shared::new(Lock::new(vec![]));
+            copies.insert(address, Literal::Map(copy.clone()));
+
+            let entries = entries.borrow().clone();
+            let entries = entries
+                .iter()
+                .map(|(key, value)| (deep_clone(key, copies), deep_clone(value, copies)))
+                .collect();
+            *copy.borrow_mut() = entries;
+            Literal::Map(copy)
+        }
+        Literal::Instance { class, fields } => {
+            let copy = Shared::new(Lock::new(vec![]));
+            let instance = Literal::Instance {
+                class: class.clone(),
+                fields: copy.clone(),
+            };
+            copies.insert(address, instance.clone());
+
+            let fields = fields.borrow().clone();
+            let fields = fields
+                .iter()
+                .map(|(name, value)| (name.clone(), deep_clone(value, copies)))
+                .collect();
+            *copy.borrow_mut() = fields;
+            instance
+        }
+        other => other.clone(),
+    };
+}
@@ -0,0 +1,38 @@
+use crate::sync::{Lock, Shared};
+
+/// Destination for everything a script prints.
+///
+/// The command line interpreter writes straight to stdout, while embedders
+/// (e.g. the browser playground) collect the output in a buffer.
+#[derive(Debug, Clone, Default)]
+pub enum Output {
+    #[default]
+    Stdout,
+    Buffer(Shared<Lock<String>>),
+}
+
+impl Output {
+    pub fn buffer() -> Self {
+        return Output::Buffer(Shared::new(Lock::new(String::new())));
+    }
+
+    pub fn write_line(&self, line: &str) {
+        match self {
+            Output::Stdout => println!("{line}"),
+            Output::Buffer(buffer) => {
+                let mut buffer = buffer.borrow_mut();
+                buffer.push_str(line);
+                buffer.push('\n');
+            }
+        }
+    }
+
+    /// Returns the collected output and clears the buffer.
+    /// Always empty when writing to stdout.
+    pub fn take(&self) -> String {
+        return match self {
+            Output::Stdout => String::new(),
+            Output::Buffer(buffer) => std::mem::take(&mut *buffer.borrow_mut()),
+        };
+    }
+}
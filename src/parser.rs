@@ -5,6 +5,22 @@ use crate::{
     token_type::TokenType,
 };
 use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Expression ids key the resolver's locals, which an interpreter keeps across
+// runs (REPL lines, embedder evals), so they must be unique across parsers.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Deepest nesting of statements and expressions the parser accepts, which
+// keeps the recursive descent (and the resolver and interpreter walking the
+// result) from overflowing the stack on malicious input.
+const MAX_NESTING: usize = 128;
+
+/// Statements of a block or of a function body.
+type Block = Vec<Box<Stmt>>;
+
+/// Instance and `static` methods of a class body.
+type ClassMembers = (Vec<Box<Stmt>>, Vec<Box<Stmt>>);
 
 #[derive(Debug)]
 enum FunctionKind {
@@ -16,7 +32,8 @@ enum FunctionKind {
 pub struct Parser {
     tokens: Vec<Token>,
     curr: usize,
-    next_id: usize,
+    depth: usize,
+    lox_compat: bool,
 }
 
 impl Parser {
@@ -24,15 +41,20 @@ impl Parser {
         return Self {
             tokens,
             curr: 0,
-            next_id: 0,
+            depth: 0,
+            lox_compat: false,
         };
     }
 
-    fn get_id(&mut self) -> usize {
-        let id = self.next_id;
-        self.next_id += 1;
+    /// Accepts the syntax of the Lox language from "Crafting Interpreters"
+    /// instead: `print value;` and `class B < A`.
+    pub fn with_lox_compat(mut self, lox_compat: bool) -> Self {
+        self.lox_compat = lox_compat;
+        return self;
+    }
 
-        return id;
+    fn get_id(&mut self) -> usize {
+        return NEXT_ID.fetch_add(1, Ordering::Relaxed);
     }
 
     pub fn parse(&mut self) -> Result<Vec<Stmt>, String> {
@@ -65,6 +87,10 @@ impl Parser {
             return self.class_declaration();
         } else if self.match_token(TokenType::Fun)? {
             return self.function(FunctionKind::Function);
+        } else if self.match_token(TokenType::Import)? {
+            return self.import_declaration();
+        } else if self.match_token(TokenType::Extend)? {
+            return self.extend_declaration();
         } else {
             return self.statement();
         }
@@ -90,11 +116,27 @@ impl Parser {
         return Ok(Stmt::Var { name, initializer });
     }
 
+    fn import_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::Identifier, "Expected module name after 'import'")?;
+        self.consume(TokenType::Semicolon, "Expected ';' after module name")?;
+
+        return Ok(Stmt::Import { name });
+    }
+
     fn class_declaration(&mut self) -> Result<Stmt, String> {
         let name = self.consume(TokenType::Identifier, "Expected name after 'class' keyword")?;
 
-        let superclass = if self.match_token(TokenType::Colon)? {
-            self.consume(TokenType::Identifier, "Expected superclass name after ':'")?;
+        let inherits = if self.lox_compat {
+            TokenType::Less
+        } else {
+            TokenType::Colon
+        };
+        let superclass = if self.match_token(inherits)? {
+            let inherits = self.previous()?.name;
+            self.consume(
+                TokenType::Identifier,
+                &format!("Expected superclass name after '{inherits}'"),
+            )?;
             Some(Expr::Variable {
                 id: self.get_id(),
                 name: self.previous()?,
@@ -104,19 +146,48 @@ impl Parser {
         };
 
         self.consume(TokenType::LeftBrace, "Expected '{{' before class body")?;
+        let (methods, static_methods) = self.class_members()?;
+        self.consume(TokenType::RightBrace, "Expected '}}' after class body")?;
 
+        return Ok(Stmt::Class {
+            name,
+            methods,
+            static_methods,
+            superclass,
+        });
+    }
+
+    /// Parses methods up to the closing brace.
+    fn class_members(&mut self) -> Result<ClassMembers, String> {
         let mut methods = Vec::new();
+        let mut static_methods = Vec::new();
+
         while !self.check(TokenType::RightBrace) {
-            let method = self.function(FunctionKind::Method)?;
-            methods.push(Box::new(method));
+            if self.match_token(TokenType::Static)? {
+                static_methods.push(Box::new(self.function(FunctionKind::Method)?));
+            } else {
+                methods.push(Box::new(self.function(FunctionKind::Method)?));
+            }
         }
 
-        self.consume(TokenType::RightBrace, "Expected '}}' after class body")?;
+        return Ok((methods, static_methods));
+    }
 
-        return Ok(Stmt::Class {
-            name,
+    fn extend_declaration(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::Identifier, "Expected class name after 'extend'")?;
+        let class = Expr::Variable {
+            id: self.get_id(),
+            name: self.previous()?,
+        };
+
+        self.consume(TokenType::LeftBrace, "Expected '{' before extend body")?;
+        let (methods, static_methods) = self.class_members()?;
+        self.consume(TokenType::RightBrace, "Expected '}' after extend body")?;
+
+        return Ok(Stmt::Extend {
+            class,
             methods,
-            superclass,
+            static_methods,
         });
     }
 
@@ -132,7 +203,7 @@ impl Parser {
 
         if !self.check(TokenType::RightParen) {
             loop {
-                let location = self.peek().unwrap().line;
+                let location = self.line();
                 if params.len() >= 255 {
                     return Err(format!(
                         "Line {location}: Can't have more than 255 parameters"
@@ -155,15 +226,16 @@ impl Parser {
             &format!("Expcected '{{' before {kind:?} name"),
         )?;
 
-        let body = match self.block_statement()? {
-            Stmt::Block { statements } => statements,
-            _ => panic!("Block statement parsed something that was not a block"),
-        };
+        let body = self.nested(Self::block)?;
 
         return Ok(Stmt::Function { name, params, body });
     }
 
     fn statement(&mut self) -> Result<Stmt, String> {
+        return self.nested(Self::statement_kind);
+    }
+
+    fn statement_kind(&mut self) -> Result<Stmt, String> {
         if self.match_token(TokenType::Print)? {
             return self.print_statement();
         } else if self.match_token(TokenType::LeftBrace)? {
@@ -174,14 +246,33 @@ impl Parser {
             return self.while_statement();
         } else if self.match_token(TokenType::For)? {
             return self.for_statement();
+        } else if self.match_token(TokenType::With)? {
+            return self.with_statement();
         } else if self.match_token(TokenType::Return)? {
             return self.return_statement();
+        } else if self.match_token(TokenType::Break)? {
+            let keyword = self.previous()?;
+            self.consume(TokenType::Semicolon, "Expected ';' after 'break'")?;
+            return Ok(Stmt::Break { keyword });
+        } else if self.match_token(TokenType::Continue)? {
+            let keyword = self.previous()?;
+            self.consume(TokenType::Semicolon, "Expected ';' after 'continue'")?;
+            return Ok(Stmt::Continue { keyword });
         } else {
             return self.expression_statement();
         }
     }
 
     fn print_statement(&mut self) -> Result<Stmt, String> {
+        if self.lox_compat {
+            let expression = self.expression()?;
+            self.consume(TokenType::Semicolon, "Expected ';' after value")?;
+            return Ok(Stmt::Print {
+                expression,
+                arguments: vec![],
+            });
+        }
+
         self.consume(TokenType::LeftParen, "Expected '(' after 'print'")?;
 
         let expr = self.expression()?;
@@ -203,14 +294,21 @@ impl Parser {
 
         // Control the number of parameters in the print statement
         // A parameter is in this format: print("{}", a)
-        let expected_parameters = expr.to_string().matches("{}").count();
-        let found_parameters = arguments.len();
-
-        if expected_parameters != found_parameters {
-            return Err(format!(
-                "Invalid parameters length: {} expected, {} found",
-                expected_parameters, found_parameters
-            ));
+        // Only a literal template can be checked while parsing
+        if let Expr::Literal {
+            value: Literal::String(template),
+            ..
+        } = &expr
+        {
+            let expected_parameters = template.matches("{}").count();
+            let found_parameters = arguments.len();
+
+            if expected_parameters != found_parameters {
+                return Err(format!(
+                    "Invalid parameters length: {} expected, {} found",
+                    expected_parameters, found_parameters
+                ));
+            }
         }
 
         return Ok(Stmt::Print {
@@ -227,6 +325,12 @@ impl Parser {
     }
 
     fn block_statement(&mut self) -> Result<Stmt, String> {
+        return Ok(Stmt::Block {
+            statements: self.block()?,
+        });
+    }
+
+    fn block(&mut self) -> Result<Block, String> {
         let mut statements = Vec::new();
 
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
@@ -234,10 +338,11 @@ impl Parser {
         }
 
         self.consume(TokenType::RightBrace, "Expected '}' after a block")?;
-        return Ok(Stmt::Block { statements });
+        return Ok(statements);
     }
 
     fn if_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous()?;
         self.consume(TokenType::LeftParen, "Expected ')' after 'if'")?;
         let condition = self.expression()?;
 
@@ -252,6 +357,7 @@ impl Parser {
         };
 
         return Ok(Stmt::IfStmt {
+            keyword,
             condition,
             then_branch,
             else_branch,
@@ -259,6 +365,7 @@ impl Parser {
     }
 
     fn while_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous()?;
         self.consume(TokenType::LeftParen, "Expected ')' after 'while'")?;
         let condition = self.expression()?;
 
@@ -268,10 +375,16 @@ impl Parser {
         )?;
         let body = Box::from(self.statement()?);
 
-        return Ok(Stmt::WhileStmt { condition, body });
+        return Ok(Stmt::WhileStmt {
+            keyword,
+            condition,
+            body,
+            increment: None,
+        });
     }
 
     fn for_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous()?;
         self.consume(TokenType::LeftParen, "Expected ')' after 'while'")?;
 
         let initializer = if self.match_token(TokenType::Semicolon)? {
@@ -302,15 +415,6 @@ impl Parser {
 
         let mut body = self.statement()?;
 
-        if let Some(incr) = increment {
-            body = Stmt::Block {
-                statements: vec![
-                    Box::new(body),
-                    Box::new(Stmt::Expression { expression: incr }),
-                ],
-            }
-        }
-
         let cond = match condition {
             Some(c) => c,
             None => Expr::Literal {
@@ -320,8 +424,10 @@ impl Parser {
         };
 
         body = Stmt::WhileStmt {
+            keyword,
             condition: cond,
             body: Box::new(body),
+            increment,
         };
 
         if let Some(init) = initializer {
@@ -333,6 +439,30 @@ impl Parser {
         return Ok(body);
     }
 
+    fn with_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous()?;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'with'")?;
+        self.consume(TokenType::Var, "Expected 'var' after 'with ('")?;
+        let name = self.consume(TokenType::Identifier, "Expected variable name after 'var'")?;
+        self.consume(
+            TokenType::Equal,
+            "Expected '=' after the variable of 'with'",
+        )?;
+        let initializer = self.expression()?;
+        self.consume(
+            TokenType::RightParen,
+            "Expected ')' after the value of 'with'",
+        )?;
+        let body = Box::new(self.statement()?);
+
+        return Ok(Stmt::With {
+            keyword,
+            name,
+            initializer,
+            body,
+        });
+    }
+
     pub fn return_statement(&mut self) -> Result<Stmt, String> {
         let keyword = self.previous()?;
 
@@ -348,7 +478,26 @@ impl Parser {
     }
 
     pub fn expression(&mut self) -> Result<Expr, String> {
-        return self.assignment();
+        return self.nested(Self::assignment);
+    }
+
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        self.descend()?;
+        let result = parse(self);
+        self.depth -= 1;
+
+        return result;
+    }
+
+    /// Goes one level deeper into the syntax tree, e.g. into a block, a
+    /// function body or a grouping. Chains of operators are parsed in a loop and do not count.
+    fn descend(&mut self) -> Result<(), String> {
+        if self.depth >= MAX_NESTING {
+            return Err(format!("Line {}: Too much nesting", self.line()));
+        }
+
+        self.depth += 1;
+        return Ok(());
     }
 
     pub fn function_expression(&mut self) -> Result<Expr, String> {
@@ -361,7 +510,7 @@ impl Parser {
 
         if !self.check(TokenType::RightParen) {
             loop {
-                let location = self.peek().unwrap().line;
+                let location = self.line();
                 if arguments.len() >= 255 {
                     return Err(format!(
                         "Line {location}: Can't have more than 255 parameters"
@@ -386,10 +535,7 @@ impl Parser {
             "Expected '{{' after anonymous function",
         )?;
 
-        let body = match self.block_statement()? {
-            Stmt::Block { statements } => statements,
-            _ => panic!("Block statement parsed something that was not a block"),
-        };
+        let body = self.nested(Self::block)?;
 
         return Ok(Expr::AnonFunction {
             id: self.get_id(),
@@ -399,6 +545,51 @@ impl Parser {
         });
     }
 
+    fn array_expression(&mut self) -> Result<Expr, String> {
+        let mut elements = vec![];
+
+        if !self.check(TokenType::RightBracket) {
+            loop {
+                elements.push(self.expression()?);
+
+                if !self.match_token(TokenType::Comma)? {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBracket, "Expected ']' after array elements")?;
+
+        return Ok(Expr::Array {
+            id: self.get_id(),
+            elements,
+        });
+    }
+
+    fn map_expression(&mut self) -> Result<Expr, String> {
+        let mut entries = vec![];
+
+        if !self.check(TokenType::RightBrace) {
+            loop {
+                let key = self.expression()?;
+                self.consume(TokenType::Colon, "Expected ':' after map key")?;
+                let value = self.expression()?;
+                entries.push((key, value));
+
+                if !self.match_token(TokenType::Comma)? {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expected '}' after map entries")?;
+
+        return Ok(Expr::Map {
+            id: self.get_id(),
+            entries,
+        });
+    }
+
     pub fn assignment(&mut self) -> Result<Expr, String> {
         let expr = self.or()?;
 
@@ -426,11 +617,22 @@ impl Parser {
                         value: Box::new(value),
                     });
                 }
+                Expr::Index {
+                    id: _,
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expr::IndexSet {
+                        id: self.get_id(),
+                        object,
+                        bracket,
+                        index,
+                        value: Box::new(value),
+                    });
+                }
                 _ => {
-                    return Err(format!(
-                        "Invalid assignment target: '{}'.",
-                        equals.to_string()
-                    ));
+                    return Err(format!("Invalid assignment target: '{}'.", equals));
                 }
             };
         }
@@ -485,6 +687,14 @@ impl Parser {
         }
     }
 
+    /// The line of the current token, for error messages.
+    fn line(&mut self) -> usize {
+        return match self.peek() {
+            Some(token) => token.line,
+            None => self.previous().map(|token| token.line).unwrap_or_default(),
+        };
+    }
+
     fn peek(&mut self) -> Option<Token> {
         return self.tokens.get(self.curr).cloned();
     }
@@ -498,11 +708,13 @@ impl Parser {
     }
 
     fn check(&mut self, token_type: TokenType) -> bool {
-        if self.is_at_end() || self.peek().is_none() {
+        if self.is_at_end() {
             return false;
         }
 
-        return self.peek().unwrap().token_type == token_type;
+        return self
+            .peek()
+            .is_some_and(|token| token.token_type == token_type);
     }
 
     fn advance(&mut self) -> Result<Token, String> {
@@ -538,9 +750,9 @@ impl Parser {
         while self.match_tokens(vec![TokenType::BangEqual, TokenType::EqualEqual])? {
             expr = Ok(Expr::Binary {
                 id: self.get_id(),
-                left: Box::from(expr?.clone()),
+                left: Box::from(expr?),
                 operator: self.previous()?.clone(),
-                right: Box::from(self.comparison()?.clone()),
+                right: Box::from(self.comparison()?),
             });
         }
 
@@ -558,7 +770,7 @@ impl Parser {
         ])? {
             expr = Ok(Expr::Binary {
                 id: self.get_id(),
-                left: Box::from(expr?.clone()),
+                left: Box::from(expr?),
                 operator: self.previous()?.clone(),
                 right: Box::from(self.term()?),
             });
@@ -573,7 +785,7 @@ impl Parser {
         while self.match_tokens(vec![TokenType::Minus, TokenType::Plus])? {
             expr = Ok(Expr::Binary {
                 id: self.get_id(),
-                left: Box::from(expr?.clone()),
+                left: Box::from(expr?),
                 operator: self.previous()?.clone(),
                 right: Box::from(self.factor()?),
             });
@@ -588,7 +800,7 @@ impl Parser {
         while self.match_tokens(vec![TokenType::Slash, TokenType::Star])? {
             expr = Ok(Expr::Binary {
                 id: self.get_id(),
-                left: Box::from(expr?.clone()),
+                left: Box::from(expr?),
                 operator: self.previous()?,
                 right: Box::from(self.unary()?),
             });
@@ -602,7 +814,7 @@ impl Parser {
             return Ok(Expr::Unary {
                 id: self.get_id(),
                 operator: self.previous()?,
-                right: Box::from(self.unary()?),
+                right: Box::from(self.nested(Self::unary)?),
             });
         }
 
@@ -615,6 +827,16 @@ impl Parser {
         loop {
             if self.match_token(TokenType::LeftParen)? {
                 expr = self.finish_call(expr)?;
+            } else if self.match_token(TokenType::LeftBracket)? {
+                let bracket = self.previous()?;
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expected ']' after index")?;
+                expr = Expr::Index {
+                    id: self.get_id(),
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else if self.match_token(TokenType::Dot)? {
                 let name =
                     self.consume(TokenType::Identifier, "Expected proprety name after '.'")?;
@@ -639,7 +861,7 @@ impl Parser {
                 arguments.push(self.expression()?);
 
                 if arguments.len() >= 255 {
-                    let location = self.peek().unwrap().line;
+                    let location = self.line();
                     return Err(format!(
                         "Line {location}: Can't have more than 255 arguments"
                     ));
@@ -677,6 +899,7 @@ impl Parser {
                 | TokenType::True
                 | TokenType::Nil
                 | TokenType::Number
+                | TokenType::Decimal
                 | TokenType::String => {
                     self.advance()?;
                     let token = self.previous()?;
@@ -696,6 +919,14 @@ impl Parser {
                     self.advance()?;
                     self.function_expression()?
                 }
+                TokenType::LeftBracket => {
+                    self.advance()?;
+                    self.array_expression()?
+                }
+                TokenType::LeftBrace => {
+                    self.advance()?;
+                    self.map_expression()?
+                }
                 TokenType::This => {
                     self.advance()?;
 
@@ -756,11 +987,15 @@ impl Parser {
             match token_type {
                 TokenType::Class
                 | TokenType::Fun
+                | TokenType::Import
+                | TokenType::Extend
                 | TokenType::Var
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
                 | TokenType::Print
+                | TokenType::Break
+                | TokenType::Continue
                 | TokenType::Return => return Ok(()),
                 _ => (),
             }
@@ -0,0 +1,29 @@
+/// Capabilities a script only gets when the host grants them explicitly.
+/// Everything is denied by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Permissions {
+    /// Allows `set_env` to modify the process environment.
+    pub set_env: bool,
+    /// Allows `exec` to run subprocesses.
+    pub exec: bool,
+    /// Allows `eval` to run code built at runtime.
+    pub eval: bool,
+    /// Allows `mkdir`, `remove_dir` and `remove_file` to change the file
+    /// system.
+    pub fs_write: bool,
+}
+
+impl Permissions {
+    /// Grants the permission called `name`, e.g. from a command line flag.
+    pub fn allow(&mut self, name: &str) -> Result<(), String> {
+        match name {
+            "set_env" => self.set_env = true,
+            "exec" => self.exec = true,
+            "eval" => self.eval = true,
+            "fs_write" => self.fs_write = true,
+            _ => return Err(format!("Unknown permission '{name}'")),
+        }
+
+        return Ok(());
+    }
+}
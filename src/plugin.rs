@@ -0,0 +1,337 @@
+//! Native extensions loaded from shared libraries at startup.
+//!
+//! Plugins only exchange C types with the interpreter, so a plugin keeps
+//! working with interpreters built by another compiler or another version
+//! of this crate as long as [`PLUGIN_API_VERSION`] matches. A plugin
+//! exports two functions, declared for C plugins in `include/lang.h`:
+//!
+//! - `lang_plugin_api_version`, returning the API version it was built for
+//! - `lang_plugin_register`, receiving the [`PluginHost`] to register its
+//!   natives and scripts with and returning 0 on success
+//!
+//! A Rust plugin is a `cdylib` depending on this crate that implements
+//! [`Plugin`] and exports it with [`declare_plugin!`]:
+//!
+//! ``` ignore
+//! use programming_language::{declare_plugin, expr::Literal, plugin::{Plugin, PluginRegistry}};
+//!
+//! #[derive(Default)]
+//! struct Greeter;
+//!
+//! impl Plugin for Greeter {
+//!     fn register(&self, registry: &mut PluginRegistry) -> Result<(), String> {
+//!         registry.define_native("greet", 1, |args| {
+//!             return Ok(Literal::String(format!("Hello {}", args[0])));
+//!         });
+//!         return registry.define_script("class Greeter { hi(name) { return greet(name); } }");
+//!     }
+//! }
+//!
+//! declare_plugin!(Greeter, Greeter::default);
+//! ```
+//!
+//! Natives of a plugin receive and return nil, booleans, numbers and
+//! strings, any other value is passed as its string representation.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    ptr, slice,
+};
+
+use libloading::Library;
+
+pub use crate::c_value::{LangValue, LangValueKind};
+use crate::{
+    c_value::{to_c_string, UserData},
+    expr::Literal,
+    interpreter::Interpreter,
+};
+
+/// Bumped on every incompatible change to [`PluginHost`] or to the
+/// functions a plugin exports.
+pub const PLUGIN_API_VERSION: u32 = 2;
+
+const API_VERSION_SYMBOL: &[u8] = b"lang_plugin_api_version\0";
+const REGISTER_SYMBOL: &[u8] = b"lang_plugin_register\0";
+
+/// A native function of a plugin. It returns 0 and its value in `result`,
+/// or anything else and the error message as a string in `result`. A
+/// string in `result` has to stay valid until the plugin is called again
+/// on the same thread.
+pub type PluginNative = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    args: *const LangValue,
+    argc: usize,
+    result: *mut LangValue,
+) -> c_int;
+
+/// What the interpreter hands to `lang_plugin_register`. Every function
+/// takes `context` as its first argument.
+#[repr(C)]
+pub struct PluginHost {
+    pub context: *mut c_void,
+    /// Defines a global native function `name` forwarding its arguments,
+    /// together with `user_data`, to `native`. Returns 0 on success.
+    pub define_native: unsafe extern "C" fn(
+        context: *mut c_void,
+        name: *const c_char,
+        arity: usize,
+        native: PluginNative,
+        user_data: *mut c_void,
+    ) -> c_int,
+    /// Runs script code in the global scope. Returns null on success or the
+    /// error message, valid until the host is called again.
+    pub define_script:
+        unsafe extern "C" fn(context: *mut c_void, source: *const c_char) -> *const c_char,
+    /// Sets the message reported when `lang_plugin_register` fails.
+    pub fail: unsafe extern "C" fn(context: *mut c_void, message: *const c_char),
+}
+
+pub trait Plugin {
+    fn register(&self, registry: &mut PluginRegistry) -> Result<(), String>;
+}
+
+/// Exports `$plugin` so that it can be found by [`load_plugin`].
+#[macro_export]
+macro_rules! declare_plugin {
+    ($plugin:ty, $constructor:path) => {
+        #[no_mangle]
+        pub extern "C" fn lang_plugin_api_version() -> u32 {
+            return $crate::plugin::PLUGIN_API_VERSION;
+        }
+
+        /// # Safety
+        /// `host` must point to a valid `PluginHost`.
+        #[no_mangle]
+        pub unsafe extern "C" fn lang_plugin_register(
+            host: *const $crate::plugin::PluginHost,
+        ) -> ::std::ffi::c_int {
+            let plugin: $plugin = $constructor();
+            return $crate::plugin::register(&plugin, host);
+        }
+    };
+}
+
+/// What a plugin is allowed to add to the interpreter.
+pub struct PluginRegistry<'a> {
+    host: &'a PluginHost,
+}
+
+type PluginFn = Box<dyn Fn(&[Literal]) -> Result<Literal, String>>;
+
+thread_local! {
+    /// Strings of the last value returned by a native of this plugin.
+    static RESULT_STRINGS: RefCell<Vec<CString>> = const { RefCell::new(vec![]) };
+}
+
+impl PluginRegistry<'_> {
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&[Literal]) -> Result<Literal, String> + 'static,
+    ) {
+        // Never freed, like the library itself
+        let fun: *mut PluginFn = Box::into_raw(Box::new(Box::new(fun)));
+        let name = to_c_string(name.to_string());
+        unsafe {
+            (self.host.define_native)(
+                self.host.context,
+                name.as_ptr(),
+                arity,
+                call_native,
+                fun.cast(),
+            );
+        }
+    }
+
+    /// Runs script code in the global scope, typically to define classes
+    /// whose methods are backed by the plugin's natives.
+    pub fn define_script(&mut self, src: &str) -> Result<(), String> {
+        let src = to_c_string(src.to_string());
+        let err = unsafe { (self.host.define_script)(self.host.context, src.as_ptr()) };
+        if err.is_null() {
+            return Ok(());
+        }
+
+        return Err(unsafe { CStr::from_ptr(err) }
+            .to_string_lossy()
+            .into_owned());
+    }
+}
+
+unsafe extern "C" fn call_native(
+    user_data: *mut c_void,
+    args: *const LangValue,
+    argc: usize,
+    result: *mut LangValue,
+) -> c_int {
+    let fun = &*(user_data as *const PluginFn);
+    let args: Vec<Literal> = match argc {
+        0 => vec![],
+        _ => slice::from_raw_parts(args, argc)
+            .iter()
+            .map(|arg| arg.to_literal())
+            .collect(),
+    };
+
+    let (status, value) = match panic::catch_unwind(AssertUnwindSafe(|| fun(&args))) {
+        Ok(Ok(value)) => (0, value),
+        Ok(Err(err)) => (1, Literal::String(err)),
+        Err(_) => (1, Literal::String("Plugin native panicked".to_string())),
+    };
+
+    RESULT_STRINGS.with_borrow_mut(|strings| {
+        strings.clear();
+        *result = LangValue::from_literal(&value, strings);
+    });
+    return status;
+}
+
+/// Called by the `lang_plugin_register` of [`declare_plugin!`].
+///
+/// # Safety
+/// `host` must point to a valid [`PluginHost`].
+#[doc(hidden)]
+pub unsafe fn register(plugin: &dyn Plugin, host: *const PluginHost) -> c_int {
+    let Some(host) = host.as_ref() else {
+        return -1;
+    };
+
+    let mut registry = PluginRegistry { host };
+    let err = match panic::catch_unwind(AssertUnwindSafe(|| plugin.register(&mut registry))) {
+        Ok(Ok(_)) => return 0,
+        Ok(Err(err)) => err,
+        Err(_) => "the plugin panicked".to_string(),
+    };
+
+    (host.fail)(host.context, to_c_string(err).as_ptr());
+    return 1;
+}
+
+/// State of the interpreter behind [`PluginHost::context`].
+struct HostContext<'a> {
+    interpreter: &'a mut Interpreter,
+    script_error: CString,
+    error: Option<String>,
+}
+
+unsafe extern "C" fn host_define_native(
+    context: *mut c_void,
+    name: *const c_char,
+    arity: usize,
+    native: PluginNative,
+    user_data: *mut c_void,
+) -> c_int {
+    let context = &mut *(context as *mut HostContext);
+    if name.is_null() {
+        return -1;
+    }
+
+    let name = CStr::from_ptr(name).to_string_lossy();
+    let user_data = UserData(user_data);
+    context
+        .interpreter
+        .define_native(&name, arity, move |args: &[Literal]| {
+            let mut strings = vec![];
+            let values: Vec<LangValue> = args
+                .iter()
+                .map(|arg| LangValue::from_literal(arg, &mut strings))
+                .collect();
+
+            let mut result = LangValue::nil();
+            let status = unsafe {
+                native(
+                    user_data.as_ptr(),
+                    values.as_ptr(),
+                    values.len(),
+                    &mut result,
+                )
+            };
+            let result = unsafe { result.to_literal() };
+
+            return match status {
+                0 => Ok(result),
+                _ => Err(result.to_string()),
+            };
+        });
+
+    return 0;
+}
+
+unsafe extern "C" fn host_define_script(
+    context: *mut c_void,
+    source: *const c_char,
+) -> *const c_char {
+    let context = &mut *(context as *mut HostContext);
+    if source.is_null() {
+        return ptr::null();
+    }
+
+    // Scripts are always run by the host's code: the plugin carries its own
+    // copy of this crate, including the parser's expression id counter
+    let source = CStr::from_ptr(source).to_string_lossy();
+    return match context.interpreter.run(&source) {
+        Ok(_) => ptr::null(),
+        Err(err) => {
+            context.script_error = to_c_string(err);
+            context.script_error.as_ptr()
+        }
+    };
+}
+
+unsafe extern "C" fn host_fail(context: *mut c_void, message: *const c_char) {
+    let context = &mut *(context as *mut HostContext);
+    if !message.is_null() {
+        context.error = Some(CStr::from_ptr(message).to_string_lossy().into_owned());
+    }
+}
+
+/// Loads the plugin at `path` and registers it into `interpreter`.
+///
+/// The library is never unloaded: the natives it registered can be
+/// referenced for as long as the process runs.
+pub fn load_plugin(interpreter: &mut Interpreter, path: &Path) -> Result<(), String> {
+    let library = unsafe { Library::new(path) }
+        .map_err(|err| format!("Cannot load plugin '{}': {err}", path.display()))?;
+    let library: &'static Library = Box::leak(Box::new(library));
+
+    let api_version = unsafe { library.get::<extern "C" fn() -> u32>(API_VERSION_SYMBOL) }
+        .map_err(|err| format!("'{}' is not a plugin: {err}", path.display()))?;
+    let api_version = api_version();
+    if api_version != PLUGIN_API_VERSION {
+        return Err(format!(
+            "Plugin '{}' was built for plugin API {api_version}, expected {PLUGIN_API_VERSION}",
+            path.display(),
+        ));
+    }
+
+    let register =
+        unsafe { library.get::<unsafe extern "C" fn(*const PluginHost) -> c_int>(REGISTER_SYMBOL) }
+            .map_err(|err| format!("'{}' is not a plugin: {err}", path.display()))?;
+
+    let mut context = HostContext {
+        interpreter,
+        script_error: CString::default(),
+        error: None,
+    };
+    let host = PluginHost {
+        context: (&mut context as *mut HostContext).cast(),
+        define_native: host_define_native,
+        define_script: host_define_script,
+        fail: host_fail,
+    };
+
+    if unsafe { register(&host) } != 0 {
+        return Err(format!(
+            "Plugin '{}' failed to register: {}",
+            path.display(),
+            context.error.unwrap_or_else(|| "unknown error".to_string())
+        ));
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use crate::{
+    diagnostics::Diagnostics, environment::Environment, lexer::Lexer, parser::Parser,
+    resolver::Resolver, stmt::Stmt, symbols::SymbolTable, sync::Shared,
+};
+
+/// A lexed, parsed and resolved script.
+///
+/// Cloning is cheap, so one compilation can be run by any number of
+/// isolated interpreters. With the `sync` feature it can also be shared
+/// between threads.
+#[derive(Debug, Clone)]
+pub struct Program {
+    stmts: Shared<Vec<Stmt>>,
+    locals: Shared<HashMap<usize, usize>>,
+    diagnostics: Shared<Diagnostics>,
+    symbols: Shared<SymbolTable>,
+}
+
+impl Program {
+    pub fn compile(src: &str) -> Result<Self, String> {
+        return Self::compile_with(src, Resolver::new(), false);
+    }
+
+    /// Compiles `src` as if it was written where `environment` is, so that
+    /// it can use the local variables visible there. References to names
+    /// that are not defined anywhere are reported as warnings.
+    pub fn compile_in(src: &str, environment: &Environment) -> Result<Self, String> {
+        let resolver =
+            Resolver::with_scopes(environment.scopes()).with_globals(environment.global().names());
+        return Self::compile_with(src, resolver, environment.is_lox_compat());
+    }
+
+    fn compile_with(src: &str, resolver: Resolver, lox_compat: bool) -> Result<Self, String> {
+        let mut lexer = Lexer::new(src);
+        let tokens = lexer.scan_tokens()?;
+
+        let mut parser = Parser::new(tokens).with_lox_compat(lox_compat);
+        let stmts = parser.parse()?;
+
+        let resolution = resolver.analyze(&stmts.iter().collect())?;
+
+        return Ok(Self {
+            stmts: Shared::new(stmts),
+            locals: Shared::new(resolution.locals),
+            diagnostics: Shared::new(resolution.diagnostics),
+            symbols: Shared::new(resolution.symbols),
+        });
+    }
+
+    pub fn stmts(&self) -> &[Stmt] {
+        return &self.stmts;
+    }
+
+    pub fn locals(&self) -> &HashMap<usize, usize> {
+        return &self.locals;
+    }
+
+    /// Warnings found while resolving, they only stop the program from
+    /// running when denied by [`Lints`](crate::diagnostics::Lints).
+    pub fn diagnostics(&self) -> &Diagnostics {
+        return &self.diagnostics;
+    }
+
+    /// Declarations, references and scopes of the program, e.g. for
+    /// editor tooling.
+    pub fn symbols(&self) -> &SymbolTable {
+        return &self.symbols;
+    }
+}
@@ -0,0 +1,140 @@
+//! Python bindings, built with `maturin develop --features python`.
+//!
+//! ``` python
+//! from programming_language import Interpreter
+//!
+//! interpreter = Interpreter()
+//! interpreter.register("double", 1, lambda x: x * 2)
+//! print(interpreter.eval('print("{}", double(21));'))
+//! ```
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyTypeError},
+    prelude::*,
+    types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+    IntoPyObjectExt,
+};
+
+use crate::{
+    expr::Literal,
+    interpreter::Interpreter,
+    output::Output,
+    sync::{Lock, Shared},
+};
+
+fn to_python(py: Python<'_>, literal: &Literal) -> PyResult<Py<PyAny>> {
+    return match literal {
+        Literal::Nil => Ok(py.None()),
+        Literal::True => true.into_py_any(py),
+        Literal::False => false.into_py_any(py),
+        Literal::Number(x) => x.into_py_any(py),
+        Literal::String(x) => x.into_py_any(py),
+        Literal::Array(elements) => {
+            let elements = elements
+                .borrow()
+                .iter()
+                .map(|element| to_python(py, element))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, elements)?.into_py_any(py)
+        }
+        Literal::Map(entries) => {
+            let dict = PyDict::new(py);
+            for (key, value) in entries.borrow().iter() {
+                dict.set_item(to_python(py, key)?, to_python(py, value)?)?;
+            }
+            dict.into_py_any(py)
+        }
+        other => other.to_string().into_py_any(py),
+    };
+}
+
+fn from_python(value: &Bound<'_, PyAny>) -> PyResult<Literal> {
+    if value.is_none() {
+        return Ok(Literal::Nil);
+    } else if value.is_instance_of::<PyBool>() {
+        return Ok(Literal::from_bool(value.extract()?));
+    } else if value.is_instance_of::<PyInt>() || value.is_instance_of::<PyFloat>() {
+        return Ok(Literal::Number(value.extract()?));
+    } else if value.is_instance_of::<PyString>() {
+        return Ok(Literal::String(value.extract()?));
+    } else if let Ok(list) = value.cast::<PyList>() {
+        let elements = list
+            .iter()
+            .map(|element| from_python(&element))
+            .collect::<PyResult<Vec<_>>>()?;
+        return Ok(Literal::Array(Shared::new(Lock::new(elements))));
+    } else if let Ok(dict) = value.cast::<PyDict>() {
+        let entries = dict
+            .iter()
+            .map(|(key, value)| Ok((from_python(&key)?, from_python(&value)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        return Ok(Literal::Map(Shared::new(Lock::new(entries))));
+    }
+
+    return Err(PyTypeError::new_err(format!(
+        "Cannot convert '{}' to a script value",
+        value.get_type().name()?
+    )));
+}
+
+#[pyclass(name = "Interpreter", unsendable)]
+pub struct PyInterpreter {
+    interpreter: Interpreter,
+    output: Output,
+}
+
+#[pymethods]
+impl PyInterpreter {
+    #[new]
+    fn new() -> Self {
+        let output = Output::buffer();
+        return Self {
+            interpreter: Interpreter::with_output(output.clone()),
+            output,
+        };
+    }
+
+    /// Runs `source` and returns everything it printed.
+    fn eval(&mut self, source: &str) -> PyResult<String> {
+        let result = self.interpreter.run(source);
+        let output = self.output.take();
+
+        return match result {
+            Ok(_) => Ok(output),
+            Err(err) => Err(PyRuntimeError::new_err(err)),
+        };
+    }
+
+    /// Returns the value of the global variable `name`.
+    fn get(&self, py: Python<'_>, name: &str) -> PyResult<Py<PyAny>> {
+        return match self.interpreter.environment.get_global(name) {
+            Some(value) => to_python(py, &value),
+            None => Err(PyRuntimeError::new_err(format!(
+                "Undefined variable '{name}'"
+            ))),
+        };
+    }
+
+    /// Makes the Python callable `fun` available to scripts as `name`.
+    fn register(&mut self, name: &str, arity: usize, fun: Py<PyAny>) {
+        self.interpreter.define_native(name, arity, move |args| {
+            return Python::attach(|py| {
+                let args = args
+                    .iter()
+                    .map(|arg| to_python(py, arg))
+                    .collect::<PyResult<Vec<_>>>()
+                    .map_err(|err| err.to_string())?;
+                let args = PyTuple::new(py, args).map_err(|err| err.to_string())?;
+
+                let result = fun.call1(py, args).map_err(|err| err.to_string())?;
+                return from_python(result.bind(py)).map_err(|err| err.to_string());
+            });
+        });
+    }
+}
+
+#[pymodule]
+fn programming_language(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyInterpreter>()?;
+    return Ok(());
+}
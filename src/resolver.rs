@@ -1,19 +1,47 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{expr::Expr, stmt::Stmt, token::Token};
+use crate::{
+    diagnostics::{Diagnostics, Lint},
+    environment::Environment,
+    expr::{Expr, Literal},
+    stmt::Stmt,
+    symbols::{Access, Reference, SymbolKind, SymbolTable},
+    token::Token,
+};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum FunctionType {
     None,
     Function,
     Method,
+    Initializer,
+    Static,
 }
 
 #[derive(Debug)]
 pub struct Resolver {
     scopes: Vec<HashMap<String, bool>>,
+    // Symbol table ids of `scopes` and of the declarations they hold
+    scope_ids: Vec<usize>,
+    bindings: Vec<HashMap<String, usize>>,
     current_function: FunctionType,
+    in_loop: bool,
     locals: HashMap<usize, usize>,
+    diagnostics: Diagnostics,
+    symbols: SymbolTable,
+    // Globals existing before the program runs, `None` when unknown
+    globals: Option<HashSet<String>>,
+    // References to globals, linked once all of them are declared
+    global_references: Vec<Reference>,
+}
+
+/// Everything the resolver found out about a program.
+#[derive(Debug)]
+pub struct Resolution {
+    /// Expression id to the number of scopes between it and its variable.
+    pub locals: HashMap<usize, usize>,
+    pub diagnostics: Diagnostics,
+    pub symbols: SymbolTable,
 }
 
 impl Default for Resolver {
@@ -26,14 +54,61 @@ impl Resolver {
     pub fn new() -> Self {
         return Self {
             scopes: Vec::new(),
+            scope_ids: Vec::new(),
+            bindings: Vec::new(),
             current_function: FunctionType::None,
+            in_loop: false,
             locals: HashMap::new(),
+            diagnostics: Diagnostics::default(),
+            symbols: SymbolTable::default(),
+            globals: None,
+            global_references: Vec::new(),
         };
     }
 
-    pub fn resolve(mut self, stmts: &Vec<&Stmt>) -> Result<HashMap<usize, usize>, String> {
+    /// A resolver for code running inside already existing local scopes,
+    /// e.g. a string passed to `eval`.
+    pub fn with_scopes(scopes: Vec<HashSet<String>>) -> Self {
+        let mut resolver = Self::new();
+        if scopes.iter().any(|scope| scope.contains("this")) {
+            resolver.current_function = FunctionType::Method;
+        }
+        for scope in scopes {
+            resolver.begin_scope();
+            *resolver.scopes.last_mut().expect("Cannot get last scope") =
+                scope.into_iter().map(|name| (name, true)).collect();
+        }
+        return resolver;
+    }
+
+    /// Makes the resolver warn about references to globals that are neither
+    /// in `globals` (e.g. the natives of an interpreter) nor declared by the
+    /// program itself.
+    pub fn with_globals(mut self, globals: HashSet<String>) -> Self {
+        self.globals = Some(globals);
+        return self;
+    }
+
+    pub fn resolve(self, stmts: &Vec<&Stmt>) -> Result<HashMap<usize, usize>, String> {
+        return Ok(self.analyze(stmts)?.locals);
+    }
+
+    /// Like [`Resolver::resolve`], also returning the symbol table and
+    /// warnings about code that is valid but most likely a mistake, e.g.
+    /// unused local variables.
+    pub fn analyze(mut self, stmts: &Vec<&Stmt>) -> Result<Resolution, String> {
         self.resolve_many(stmts)?;
-        return Ok(self.locals);
+        while !self.scopes.is_empty() {
+            self.end_scope();
+        }
+        self.link_globals();
+
+        self.diagnostics.sort();
+        return Ok(Resolution {
+            locals: self.locals,
+            diagnostics: self.diagnostics,
+            symbols: self.symbols,
+        });
     }
 
     fn resolve_internal(&mut self, stmt: &Stmt) -> Result<(), String> {
@@ -49,30 +124,113 @@ impl Resolver {
                 body: _,
             } => self.resolve_function(stmt, FunctionType::Function)?,
             Stmt::Expression { expression } => self.resolve_expr(expression)?,
-            Stmt::IfStmt {
-                condition: _,
-                then_branch: _,
-                else_branch: _,
-            } => self.resolve_if_stmt(stmt)?,
+            Stmt::IfStmt { .. } => self.resolve_if_stmt(stmt)?,
             Stmt::Print { .. } => self.resolve_print(stmt)?,
+            Stmt::Import { name } => {
+                self.declare(name, SymbolKind::Import)?;
+                self.define(name);
+            }
             Stmt::ReturnStmt { keyword: _, value } => {
                 if self.current_function == FunctionType::None {
                     return Err("Return statement not allowed outside of a function".to_string());
                 }
 
                 if let Some(value) = value {
+                    if self.current_function == FunctionType::Initializer {
+                        return Err("Cannot return a value from an initializer".to_string());
+                    }
                     self.resolve_expr(value)?;
                 }
             }
-            Stmt::WhileStmt { condition, body } => {
+            Stmt::WhileStmt {
+                keyword,
+                condition,
+                body,
+                increment,
+            } => {
+                // `while (true)` is how infinite loops are written
+                if !matches!(
+                    condition,
+                    Expr::Literal {
+                        value: Literal::True,
+                        ..
+                    }
+                ) {
+                    self.check_condition(keyword, condition);
+                }
                 self.resolve_expr(condition)?;
+
+                let enclosing_loop = self.in_loop;
+                self.in_loop = true;
+                self.resolve_internal(body)?;
+                self.in_loop = enclosing_loop;
+
+                if let Some(increment) = increment {
+                    self.resolve_expr(increment)?;
+                }
+            }
+            Stmt::With {
+                name,
+                initializer,
+                body,
+                ..
+            } => {
+                self.resolve_expr(initializer)?;
+
+                // Not reported as unused, `close` is called on it
+                self.begin_scope();
+                self.declare(name, SymbolKind::Resource)?;
+                self.define(name);
                 self.resolve_internal(body)?;
+                self.end_scope();
+            }
+            Stmt::Break { .. } => {
+                if !self.in_loop {
+                    return Err("Cannot use 'break' outside of a loop".to_string());
+                }
+            }
+            Stmt::Continue { .. } => {
+                if !self.in_loop {
+                    return Err("Cannot use 'continue' outside of a loop".to_string());
+                }
+            }
+            Stmt::Extend {
+                class,
+                methods,
+                static_methods,
+            } => {
+                self.resolve_expr(class)?;
+
+                // The class may have a superclass, known only at runtime
+                self.begin_scope();
+                self.scopes
+                    .last_mut()
+                    .expect("Cannot get last scope")
+                    .insert("super".to_string(), true);
+                self.begin_scope();
+                self.scopes
+                    .last_mut()
+                    .expect("Cannot read last element of scopes in resolver")
+                    .insert("this".to_string(), true);
+
+                for method in methods {
+                    self.resolve_method(method)?;
+                }
+                self.end_scope();
+
+                self.resolve_static_methods(static_methods)?;
+                self.end_scope();
             }
             Stmt::Class {
                 name,
                 methods,
+                static_methods,
                 superclass,
             } => {
+                // resolving class, in the scope enclosing the superclass one
+                self.declare(name, SymbolKind::Class)?;
+                self.define(name);
+
                 // resolving superclass
                 if let Some(superclass_expr) = superclass {
                     if let Expr::Variable {
@@ -86,17 +244,17 @@ impl Resolver {
                     }
 
                     self.resolve_expr(superclass_expr)?;
-                    self.begin_scope();
+                }
+
+                // The environment of the class, holding `super` if it has one
+                self.begin_scope();
+                if superclass.is_some() {
                     self.scopes
                         .last_mut()
                         .expect("Cannot get last scope")
                         .insert("super".to_string(), true);
                 }
 
-                // resolving class
-                self.declare(name)?;
-                self.define(name);
-
                 self.begin_scope();
                 self.scopes
                     .last_mut()
@@ -105,13 +263,12 @@ impl Resolver {
 
                 // resolving methods
                 for method in methods {
-                    self.resolve_function(method, FunctionType::Method)?;
+                    self.resolve_method(method)?;
                 }
+                self.end_scope();
 
+                self.resolve_static_methods(static_methods)?;
                 self.end_scope();
-                if superclass.is_some() {
-                    self.end_scope();
-                }
             }
         };
 
@@ -119,13 +276,75 @@ impl Resolver {
     }
 
     fn resolve_many(&mut self, stmts: &Vec<&Stmt>) -> Result<(), String> {
-        for stmt in stmts {
+        let mut reported = false;
+        for (i, stmt) in stmts.iter().enumerate() {
             self.resolve_internal(stmt)?;
+
+            if reported || i + 1 == stmts.len() {
+                continue;
+            }
+            if let Some((cause, line)) = Self::diverges(stmt) {
+                self.diagnostics.push(
+                    Lint::UnreachableCode,
+                    line,
+                    format!("Unreachable code after {cause} at line {line}"),
+                );
+                reported = true;
+            }
         }
 
         return Ok(());
     }
 
+    /// Whether the statements after `stmt` can never run, with what causes
+    /// it and where.
+    fn diverges(stmt: &Stmt) -> Option<(&'static str, usize)> {
+        return match stmt {
+            Stmt::ReturnStmt { keyword, .. } => Some(("return", keyword.line)),
+            Stmt::Break { keyword } => Some(("break", keyword.line)),
+            Stmt::Continue { keyword } => Some(("continue", keyword.line)),
+            Stmt::WhileStmt {
+                keyword,
+                condition:
+                    Expr::Literal {
+                        value: Literal::True,
+                        ..
+                    },
+                body,
+                ..
+            } if !Self::breaks(body) => Some(("infinite loop", keyword.line)),
+            Stmt::Block { statements } => statements.iter().find_map(|stmt| Self::diverges(stmt)),
+            Stmt::With { body, .. } => Self::diverges(body),
+            Stmt::IfStmt {
+                then_branch,
+                else_branch: Some(else_branch),
+                ..
+            } => {
+                Self::diverges(else_branch)?;
+                Self::diverges(then_branch)
+            }
+            _ => None,
+        };
+    }
+
+    /// Whether `stmt` contains a `break` leaving the loop it is the body of.
+    fn breaks(stmt: &Stmt) -> bool {
+        return match stmt {
+            Stmt::Break { .. } => true,
+            Stmt::Block { statements } => statements.iter().any(|stmt| Self::breaks(stmt)),
+            Stmt::With { body, .. } => Self::breaks(body),
+            Stmt::IfStmt {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                Self::breaks(then_branch)
+                    || else_branch.as_ref().is_some_and(|stmt| Self::breaks(stmt))
+            }
+            _ => false,
+        };
+    }
+
     fn resolve_block(&mut self, stmt: &Stmt) -> Result<(), String> {
         match stmt {
             Stmt::Block { statements } => {
@@ -141,7 +360,7 @@ impl Resolver {
 
     fn resolve_var(&mut self, stmt: &Stmt) -> Result<(), String> {
         if let Stmt::Var { name, initializer } = stmt {
-            self.declare(name)?;
+            self.declare(name, SymbolKind::Variable)?;
             self.resolve_expr(initializer)?;
             self.define(name);
         } else {
@@ -173,6 +392,35 @@ impl Resolver {
                 return self.resolve_expr(object);
             }
             Expr::Grouping { id: _, expression } => return self.resolve_expr(expression),
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.resolve_expr(element)?;
+                }
+
+                return Ok(());
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.resolve_expr(key)?;
+                    self.resolve_expr(value)?;
+                }
+
+                return Ok(());
+            }
+            Expr::Index { object, index, .. } => {
+                self.resolve_expr(object)?;
+                return self.resolve_expr(index);
+            }
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)?;
+                return self.resolve_expr(index);
+            }
             Expr::Literal { .. } => return Ok(()),
             Expr::Logical { left, right, .. } => {
                 self.resolve_expr(left)?;
@@ -193,13 +441,19 @@ impl Resolver {
                 return self.resolve_expr(object);
             }
             Expr::This { keyword, .. } => {
-                if self.current_function != FunctionType::Method {
+                if self.current_function == FunctionType::Static {
+                    return Err("Cannot use 'this' keyword in a static method".to_string());
+                }
+                if !self.in_method() {
                     return Err("Cannot use 'this' keyword outside of a class".to_string());
                 }
-                return self.resolve_local(keyword, expr.get_id());
+                return self.resolve_local(keyword, expr.get_id(), Access::Read);
             }
             Expr::Super { keyword, .. } => {
-                if self.current_function != FunctionType::Method {
+                if self.current_function == FunctionType::Static {
+                    return Err("Cannot use 'super' keyword in a static method".to_string());
+                }
+                if !self.in_method() {
                     return Err("Cannot use 'super' keyword outside of a class".to_string());
                 }
 
@@ -211,7 +465,7 @@ impl Resolver {
                     );
                 }
 
-                return self.resolve_local(keyword, expr.get_id());
+                return self.resolve_local(keyword, expr.get_id(), Access::Read);
             }
         };
     }
@@ -225,7 +479,9 @@ impl Resolver {
                 arguments: _,
             } => {
                 match callee.as_ref() {
-                    Expr::Variable { id: _, name } => return self.resolve_local(name, resolve_id),
+                    Expr::Variable { id: _, name } => {
+                        return self.resolve_local(name, resolve_id, Access::Read)
+                    }
                     _ => panic!("Wrong type in resolve_expr_var"),
                 };
             }
@@ -241,33 +497,82 @@ impl Resolver {
                         return Err("Can't read local variable on its own initializer".to_string());
                     }
                 }
-                return self.resolve_local(name, resolve_id);
+                return self.resolve_local(name, resolve_id, Access::Read);
             }
             _ => panic!("Wrong type in resolve_expr_var"),
         };
     }
 
-    fn resolve_local(&mut self, name: &Token, resolve_id: usize) -> Result<(), String> {
+    fn resolve_local(
+        &mut self,
+        name: &Token,
+        resolve_id: usize,
+        access: Access,
+    ) -> Result<(), String> {
         let size = self.scopes.len();
-        if size == 0 {
-            return Ok(());
-        }
+        let reference = Reference {
+            name: name.name.clone(),
+            expr_id: resolve_id,
+            line: name.line,
+            access,
+        };
 
-        for i in (0..=size - 1).rev() {
+        for i in (0..size).rev() {
             let scope = self.scopes.get(i).expect("Cannot read from scopes");
 
             if scope.contains_key(&name.name) {
                 self.locals.insert(resolve_id, size - 1 - i);
+                // `this`, `super` and the locals around `eval` are not declared
+                if let Some(declaration) = self.bindings[i].get(&name.name) {
+                    self.symbols.add_reference(*declaration, reference);
+                }
                 return Ok(());
             }
         }
 
+        self.global_references.push(reference);
         return Ok(());
     }
 
+    /// Globals can be declared after the functions using them, so their
+    /// references are only linked once the whole program is resolved.
+    fn link_globals(&mut self) {
+        for reference in std::mem::take(&mut self.global_references) {
+            match self.symbols.global(&reference.name) {
+                Some(declaration) => self.symbols.add_reference(declaration, reference),
+                None => self.symbols.unresolved.push(reference),
+            }
+        }
+
+        self.check_undefined();
+    }
+
+    fn check_undefined(&mut self) {
+        let Some(globals) = &self.globals else {
+            return;
+        };
+
+        let mut reported = HashSet::new();
+        for name in &self.symbols.unresolved {
+            if globals.contains(&name.name) || !reported.insert((name.name.clone(), name.line)) {
+                continue;
+            }
+
+            self.diagnostics.push(
+                Lint::UndefinedVariables,
+                name.line,
+                format!("Undefined variable '{}' at line {}", name.name, name.line),
+            );
+        }
+    }
+
     fn resolve_function(&mut self, stmt: &Stmt, fn_type: FunctionType) -> Result<(), String> {
         if let Stmt::Function { name, params, body } = stmt {
-            self.declare(name)?;
+            let kind = match fn_type {
+                FunctionType::Function => SymbolKind::Function,
+                _ => SymbolKind::Method,
+            };
+            self.declare(name, kind)?;
             self.define(name);
 
             self.resolve_function_helper(
@@ -282,6 +587,32 @@ impl Resolver {
         return Ok(());
     }
 
+    fn resolve_method(&mut self, stmt: &Stmt) -> Result<(), String> {
+        return match stmt {
+            Stmt::Function { name, .. } if name.name == "init" => {
+                self.resolve_function(stmt, FunctionType::Initializer)
+            }
+            _ => self.resolve_function(stmt, FunctionType::Method),
+        };
+    }
+
+    /// Static methods are not bound to an instance, so unlike methods they
+    /// are resolved directly in the scope of the class.
+    fn resolve_static_methods(&mut self, methods: &[Box<Stmt>]) -> Result<(), String> {
+        for method in methods {
+            self.resolve_function(method, FunctionType::Static)?;
+        }
+
+        return Ok(());
+    }
+
+    fn in_method(&self) -> bool {
+        return matches!(
+            self.current_function,
+            FunctionType::Method | FunctionType::Initializer
+        );
+    }
+
     fn resolve_function_helper(
         &mut self,
         params: &[Token],
@@ -290,11 +621,13 @@ impl Resolver {
     ) -> Result<(), String> {
         let enclosing_function = self.current_function;
         self.current_function = resolving_function;
+        let enclosing_loop = self.in_loop;
+        self.in_loop = false;
 
         self.begin_scope();
 
         for param in params {
-            self.declare(param)?;
+            self.declare(param, SymbolKind::Parameter)?;
             self.define(param);
         }
 
@@ -302,6 +635,7 @@ impl Resolver {
         self.end_scope();
 
         self.current_function = enclosing_function;
+        self.in_loop = enclosing_loop;
 
         return Ok(());
     }
@@ -325,11 +659,13 @@ impl Resolver {
 
     fn resolve_if_stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
         if let Stmt::IfStmt {
+            keyword,
             condition,
             then_branch,
             else_branch,
         } = stmt
         {
+            self.check_condition(keyword, condition);
             self.resolve_expr(condition)?;
             self.resolve_internal(then_branch)?;
 
@@ -342,18 +678,96 @@ impl Resolver {
         return Ok(());
     }
 
+    /// Warns about conditions that do not depend on anything, most likely
+    /// a typo such as `=` instead of `==`.
+    fn check_condition(&mut self, keyword: &Token, condition: &Expr) {
+        let line = keyword.line;
+        let kind = &keyword.name;
+
+        if let Expr::Assign { name, .. } = Self::ungroup(condition) {
+            self.diagnostics.push(
+                Lint::ConstantConditions,
+                line,
+                format!(
+                    "Assignment to '{}' used as {kind} condition at line {line}, did you mean '=='?",
+                    name.name
+                ),
+            );
+        } else if Self::is_constant(condition) {
+            // Environment free, the expression only contains literals
+            let Ok(value) = condition.evaluate(Environment::new(HashMap::new())) else {
+                return;
+            };
+            let value = value.is_truthy() == Literal::True;
+
+            self.diagnostics.push(
+                Lint::ConstantConditions,
+                line,
+                format!("Condition of {kind} is always {value} at line {line}"),
+            );
+        }
+    }
+
+    fn ungroup(expr: &Expr) -> &Expr {
+        return match expr {
+            Expr::Grouping { expression, .. } => Self::ungroup(expression),
+            other => other,
+        };
+    }
+
+    fn is_constant(expr: &Expr) -> bool {
+        return match expr {
+            Expr::Literal { .. } => true,
+            Expr::Grouping { expression, .. } => Self::is_constant(expression),
+            Expr::Unary { right, .. } => Self::is_constant(right),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                Self::is_constant(left) && Self::is_constant(right)
+            }
+            _ => false,
+        };
+    }
+
     fn begin_scope(&mut self) {
+        let parent = self.scope_ids.last().copied().unwrap_or(0);
+        self.scope_ids.push(self.symbols.add_scope(parent));
         self.scopes.push(HashMap::new());
+        self.bindings.push(HashMap::new());
     }
 
     fn end_scope(&mut self) {
         self.scopes.pop().expect("Stack underflow");
+        self.bindings.pop().expect("Stack underflow");
+        let scope = self.scope_ids.pop().expect("Stack underflow");
+
+        self.check_unused(scope);
     }
 
-    fn declare(&mut self, name: &Token) -> Result<(), String> {
-        if self.scopes.is_empty() {
-            return Ok(());
+    /// Warns about the locals of `scope` that are never read, except for
+    /// names starting with `_`.
+    fn check_unused(&mut self, scope: usize) {
+        for id in &self.symbols.scopes[scope].declarations {
+            let declaration = &self.symbols.declarations[*id];
+            let tracked = matches!(
+                declaration.kind,
+                SymbolKind::Variable | SymbolKind::Function | SymbolKind::Parameter
+            );
+            if !tracked || declaration.name.starts_with('_') || declaration.is_read() {
+                continue;
+            }
+
+            self.diagnostics.push(
+                Lint::Unused,
+                declaration.line,
+                format!(
+                    "Unused {} '{}' at line {}",
+                    declaration.kind, declaration.name, declaration.line
+                ),
+            );
         }
+    }
+
+    fn declare(&mut self, name: &Token, kind: SymbolKind) -> Result<(), String> {
+        let scope = self.scope_ids.last().copied().unwrap_or(0);
 
         if let Some(last) = self.scopes.last_mut() {
             if last.contains_key(&name.name) {
@@ -363,6 +777,11 @@ impl Resolver {
             last.insert(name.name.clone(), false);
         }
 
+        let declaration = self.symbols.declare(scope, &name.name, kind, name.line);
+        if let Some(bindings) = self.bindings.last_mut() {
+            bindings.insert(name.name.clone(), declaration);
+        }
+
         return Ok(());
     }
 
@@ -380,7 +799,7 @@ impl Resolver {
     fn resolve_expr_assign(&mut self, expr: &Expr, resolve_id: usize) -> Result<(), String> {
         if let Expr::Assign { id: _, name, value } = expr {
             self.resolve_expr(value.as_ref())?;
-            self.resolve_local(name, resolve_id)?;
+            self.resolve_local(name, resolve_id, Access::Write)?;
         } else {
             panic!("Wrong type in resolve assign");
         }
@@ -0,0 +1,399 @@
+//! Classifies the tokens of a program for syntax highlighting, e.g. LSP
+//! semantic tokens: the lexer tells keywords, literals and operators apart
+//! and the resolver what every name refers to.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    expr::Expr,
+    lexer::Lexer,
+    program::Program,
+    stmt::Stmt,
+    symbols::{SymbolKind, SymbolTable},
+    token::Token,
+    token_type::TokenType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SemanticKind {
+    Keyword,
+    String,
+    Number,
+    Operator,
+    /// An imported module.
+    Namespace,
+    Class,
+    Function,
+    Method,
+    Parameter,
+    Variable,
+    /// A field read or written with `object.name`.
+    Property,
+}
+
+impl SemanticKind {
+    pub const ALL: [SemanticKind; 11] = [
+        SemanticKind::Keyword,
+        SemanticKind::String,
+        SemanticKind::Number,
+        SemanticKind::Operator,
+        SemanticKind::Namespace,
+        SemanticKind::Class,
+        SemanticKind::Function,
+        SemanticKind::Method,
+        SemanticKind::Parameter,
+        SemanticKind::Variable,
+        SemanticKind::Property,
+    ];
+
+    /// The name of the standard LSP token type.
+    pub fn name(&self) -> &'static str {
+        return match self {
+            SemanticKind::Keyword => "keyword",
+            SemanticKind::String => "string",
+            SemanticKind::Number => "number",
+            SemanticKind::Operator => "operator",
+            SemanticKind::Namespace => "namespace",
+            SemanticKind::Class => "class",
+            SemanticKind::Function => "function",
+            SemanticKind::Method => "method",
+            SemanticKind::Parameter => "parameter",
+            SemanticKind::Variable => "variable",
+            SemanticKind::Property => "property",
+        };
+    }
+
+    fn of_symbol(kind: SymbolKind) -> Self {
+        return match kind {
+            SymbolKind::Variable | SymbolKind::Resource => SemanticKind::Variable,
+            SymbolKind::Function => SemanticKind::Function,
+            SymbolKind::Method => SemanticKind::Method,
+            SymbolKind::Parameter => SemanticKind::Parameter,
+            SymbolKind::Class => SemanticKind::Class,
+            SymbolKind::Import => SemanticKind::Namespace,
+        };
+    }
+}
+
+impl fmt::Display for SemanticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.name());
+    }
+}
+
+/// A classified span of source. Lines and columns start from 1 and count
+/// characters, a string spanning several lines is split in one token per
+/// line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticToken {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub kind: SemanticKind,
+    pub text: String,
+}
+
+impl fmt::Display for SemanticToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "{}:{} {} {}",
+            self.line, self.column, self.kind, self.text
+        );
+    }
+}
+
+/// Classifies the tokens of `src`. When it does not compile, e.g. while
+/// being edited, every name is classified as a variable.
+pub fn classify(src: &str) -> Result<Vec<SemanticToken>, String> {
+    let names = match Program::compile(src) {
+        Ok(program) => names(&program),
+        Err(_) => HashMap::new(),
+    };
+    return classify_with(src, &names);
+}
+
+/// Classifies the tokens of `src`, already compiled as `program`, e.g. with
+/// [`Program::compile_in`].
+pub fn classify_program(src: &str, program: &Program) -> Result<Vec<SemanticToken>, String> {
+    return classify_with(src, &names(program));
+}
+
+fn classify_with(
+    src: &str,
+    names: &HashMap<(usize, usize), SemanticKind>,
+) -> Result<Vec<SemanticToken>, String> {
+    let mut tokens = vec![];
+
+    for token in Lexer::new(src).scan_tokens()? {
+        let kind = match token.token_type {
+            TokenType::Identifier => names
+                .get(&(token.line, token.column))
+                .copied()
+                .unwrap_or(SemanticKind::Variable),
+            TokenType::String => SemanticKind::String,
+            TokenType::Number | TokenType::Decimal => SemanticKind::Number,
+            TokenType::Minus
+            | TokenType::Plus
+            | TokenType::Slash
+            | TokenType::Star
+            | TokenType::Bang
+            | TokenType::BangEqual
+            | TokenType::Equal
+            | TokenType::EqualEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => SemanticKind::Operator,
+            TokenType::And
+            | TokenType::Break
+            | TokenType::Class
+            | TokenType::Continue
+            | TokenType::Else
+            | TokenType::Extend
+            | TokenType::False
+            | TokenType::Fun
+            | TokenType::For
+            | TokenType::If
+            | TokenType::Import
+            | TokenType::Nil
+            | TokenType::Or
+            | TokenType::Print
+            | TokenType::Return
+            | TokenType::Static
+            | TokenType::Super
+            | TokenType::This
+            | TokenType::True
+            | TokenType::Var
+            | TokenType::While
+            | TokenType::With => SemanticKind::Keyword,
+            _ => continue,
+        };
+
+        // The line of a token is the one where it ends
+        let first_line = token.line - token.name.matches('\n').count();
+        for (idx, text) in token.name.split('\n').enumerate() {
+            if text.is_empty() {
+                continue;
+            }
+            tokens.push(SemanticToken {
+                line: first_line + idx,
+                column: if idx == 0 { token.column } else { 1 },
+                length: text.chars().count(),
+                kind,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    return Ok(tokens);
+}
+
+/// Kinds of the names in `program`, by the line and column of their token.
+fn names(program: &Program) -> HashMap<(usize, usize), SemanticKind> {
+    let mut names = Names {
+        symbols: program.symbols(),
+        kinds: HashMap::new(),
+    };
+    for stmt in program.stmts() {
+        names.stmt(stmt);
+    }
+
+    return names.kinds;
+}
+
+struct Names<'a> {
+    symbols: &'a SymbolTable,
+    kinds: HashMap<(usize, usize), SemanticKind>,
+}
+
+impl Names<'_> {
+    fn mark(&mut self, name: &Token, kind: SemanticKind) {
+        self.kinds.insert((name.line, name.column), kind);
+    }
+
+    /// Marks a name used by the expression `expr_id` with the kind of its
+    /// declaration, when the resolver found one.
+    fn mark_reference(&mut self, expr_id: usize, name: &Token) {
+        if let Some(declaration) = self.symbols.definition(expr_id) {
+            self.mark(name, SemanticKind::of_symbol(declaration.kind));
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[Box<Stmt>]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression { expression } => self.expr(expression),
+            Stmt::Print {
+                expression,
+                arguments,
+            } => {
+                self.expr(expression);
+                arguments.iter().for_each(|argument| self.expr(argument));
+            }
+            Stmt::Var { name, initializer } => {
+                self.mark(name, SemanticKind::Variable);
+                self.expr(initializer);
+            }
+            Stmt::Block { statements } => self.stmts(statements),
+            Stmt::IfStmt {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            Stmt::WhileStmt {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
+                self.expr(condition);
+                self.stmt(body);
+                if let Some(increment) = increment {
+                    self.expr(increment);
+                }
+            }
+            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            Stmt::Function { name, params, body } => {
+                self.mark(name, SemanticKind::Function);
+                self.function(params, body);
+            }
+            Stmt::ReturnStmt { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::Class {
+                name,
+                methods,
+                static_methods,
+                superclass,
+            } => {
+                self.mark(name, SemanticKind::Class);
+                if let Some(superclass) = superclass {
+                    self.expr(superclass);
+                }
+                self.methods(methods.iter().chain(static_methods));
+            }
+            Stmt::Import { name } => self.mark(name, SemanticKind::Namespace),
+            Stmt::Extend {
+                class,
+                methods,
+                static_methods,
+            } => {
+                self.expr(class);
+                self.methods(methods.iter().chain(static_methods));
+            }
+            Stmt::With {
+                name,
+                initializer,
+                body,
+                ..
+            } => {
+                self.mark(name, SemanticKind::Variable);
+                self.expr(initializer);
+                self.stmt(body);
+            }
+        }
+    }
+
+    fn methods<'s>(&mut self, methods: impl Iterator<Item = &'s Box<Stmt>>) {
+        for method in methods {
+            if let Stmt::Function { name, params, body } = method.as_ref() {
+                self.mark(name, SemanticKind::Method);
+                self.function(params, body);
+            }
+        }
+    }
+
+    fn function(&mut self, params: &[Token], body: &[Box<Stmt>]) {
+        for param in params {
+            self.mark(param, SemanticKind::Parameter);
+        }
+        self.stmts(body);
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::AnonFunction {
+                arguments, body, ..
+            } => self.function(arguments, body),
+            Expr::Array { elements, .. } => elements.iter().for_each(|element| self.expr(element)),
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Assign { id, name, value } => {
+                self.mark_reference(*id, name);
+                self.expr(value);
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                match callee.as_ref() {
+                    // Names declared outside of the program, e.g. natives
+                    Expr::Variable { id, name } if self.symbols.definition(*id).is_none() => {
+                        self.mark(name, SemanticKind::Function);
+                    }
+                    Expr::Get { object, name, .. } => {
+                        self.expr(object);
+                        self.mark(name, SemanticKind::Method);
+                    }
+                    callee => self.expr(callee),
+                }
+                arguments.iter().for_each(|argument| self.expr(argument));
+            }
+            Expr::Get { object, name, .. } => {
+                self.expr(object);
+                self.mark(name, SemanticKind::Property);
+            }
+            Expr::Grouping { expression, .. } => self.expr(expression),
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            Expr::Literal { .. } | Expr::This { .. } => {}
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.mark(name, SemanticKind::Property);
+                self.expr(value);
+            }
+            Expr::Super { method, .. } => self.mark(method, SemanticKind::Method),
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Variable { id, name } => self.mark_reference(*id, name),
+        }
+    }
+}
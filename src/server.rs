@@ -0,0 +1,106 @@
+//! Evaluates programs received over a socket against one interpreter that
+//! lives as long as the server, e.g. for the "send to REPL" command of an
+//! editor.
+//!
+//! A request is the source of a program followed by a line with a single
+//! `.`, the response is what the program printed, its warnings and its
+//! error, if any, followed by the same line. Lines of a request or a
+//! response that start with `.` are sent with an extra `.` in front.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{interpreter::Interpreter, program::Program};
+
+const END: &str = ".";
+
+/// Serves the connections of `incoming` one after the other, e.g. those of
+/// a `TcpListener` or a `UnixListener`. A connection that fails, e.g.
+/// because the client went away or could not be accepted, is dropped and
+/// the server waits for the next one.
+///
+/// The output of the programs only goes back to the clients when the
+/// interpreter writes to an [`Output::buffer`](crate::output::Output::buffer).
+pub fn serve<S: Read + Write>(
+    interpreter: &mut Interpreter,
+    incoming: impl Iterator<Item = io::Result<S>>,
+) {
+    for stream in incoming.flatten() {
+        let _ = handle(interpreter, stream);
+    }
+}
+
+/// Answers the requests of one connection until the client closes it. A
+/// request cut short by the end of the connection is still answered.
+pub fn handle<S: Read + Write>(interpreter: &mut Interpreter, stream: S) -> io::Result<()> {
+    let mut stream = BufReader::new(stream);
+
+    while let Some(src) = read_request(&mut stream)? {
+        let response = respond(interpreter, &src);
+        stream.get_mut().write_all(response.as_bytes())?;
+        stream.get_mut().flush()?;
+    }
+
+    return Ok(());
+}
+
+fn read_request(stream: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut src = String::new();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if stream.read_line(&mut line)? == 0 {
+            return Ok((!src.is_empty()).then_some(src));
+        }
+
+        let content = line.trim_end_matches(['\r', '\n']);
+        if content == END {
+            return Ok(Some(src));
+        }
+        src += content.strip_prefix('.').unwrap_or(content);
+        src.push('\n');
+    }
+}
+
+/// Runs `src` and returns the response to send for it, ending with the
+/// line that closes it. A program that makes the interpreter panic is
+/// reported as an error, and the interpreter goes back to its global scope.
+pub fn respond(interpreter: &mut Interpreter, src: &str) -> String {
+    let mut lines: Vec<String> = vec![];
+
+    let environment = interpreter.environment.clone();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let program = Program::compile_in(src, &interpreter.environment)?;
+        let diagnostics = interpreter.lints().apply(program.diagnostics());
+        lines.extend(diagnostics.iter().map(|diagnostic| diagnostic.to_string()));
+        if diagnostics.has_errors() {
+            return Err("Not running the program because of denied warnings".to_string());
+        }
+
+        return interpreter.run_program(&program);
+    }))
+    .unwrap_or_else(|_| {
+        interpreter.environment = environment;
+        return Err("Internal interpreter error".to_string());
+    });
+
+    let output = interpreter.environment.output().take();
+    lines.extend(output.lines().map(str::to_string));
+    if let Err(err) = result {
+        lines.push(format!("Error: {err}"));
+    }
+
+    let mut response = String::new();
+    for line in lines.iter().flat_map(|line| line.lines()) {
+        if line.starts_with('.') {
+            response.push('.');
+        }
+        response += line;
+        response.push('\n');
+    }
+
+    return response + END + "\n";
+}
@@ -0,0 +1,279 @@
+//! Writes syntax trees back as source code, e.g. for the bundler and the
+//! explain mode.
+
+use crate::{
+    expr::{Expr, Literal},
+    stmt::Stmt,
+    token::Token,
+};
+
+const INDENT: &str = "    ";
+
+/// Changes to the source being written. Every method returns `None` to
+/// write the node as it is.
+pub trait Rewrite {
+    /// Source replacing `expr`, written at indentation `level`.
+    fn expr(&self, _expr: &Expr, _level: usize) -> Option<String> {
+        return None;
+    }
+
+    /// Source replacing `stmt`, an empty string drops it.
+    fn stmt(&self, _stmt: &Stmt, _level: usize) -> Option<String> {
+        return None;
+    }
+
+    /// Name of a variable, function or class declared in the top-level
+    /// scope.
+    fn global(&self, name: &Token) -> String {
+        return name.name.clone();
+    }
+}
+
+/// Writes a whole program, one top-level statement per line.
+pub fn program(stmts: &[Stmt], rewrite: &dyn Rewrite) -> String {
+    return stmts
+        .iter()
+        .map(|stmt| stmt_at(stmt, 0, true, rewrite))
+        .filter(|stmt| !stmt.is_empty())
+        .map(|stmt| format!("{stmt}\n"))
+        .collect();
+}
+
+/// Writes `stmt` without the indentation of its first line, nested lines
+/// are indented one more `level` than it.
+pub fn stmt(stmt: &Stmt, level: usize, rewrite: &dyn Rewrite) -> String {
+    return stmt_at(stmt, level, false, rewrite);
+}
+
+fn stmt_at(stmt: &Stmt, level: usize, top_level: bool, rewrite: &dyn Rewrite) -> String {
+    if let Some(source) = rewrite.stmt(stmt, level) {
+        return source;
+    }
+
+    let name = |name: &Token| match top_level {
+        true => rewrite.global(name),
+        false => name.name.clone(),
+    };
+    let expr = |expr: &Expr| self::expr(expr, level, rewrite);
+    let nested = |stmt: &Stmt| self::stmt(stmt, level, rewrite);
+
+    return match stmt {
+        Stmt::Expression { expression } => format!("{};", expr(expression)),
+        Stmt::Print {
+            expression,
+            arguments,
+        } => {
+            let exprs: Vec<String> = std::iter::once(expression)
+                .chain(arguments)
+                .map(expr)
+                .collect();
+            format!("print({});", exprs.join(", "))
+        }
+        Stmt::Var {
+            name: var,
+            initializer,
+        } => {
+            format!("var {} = {};", name(var), expr(initializer))
+        }
+        Stmt::Block { statements } => block(statements, level, rewrite),
+        Stmt::IfStmt {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            let mut source = format!("if ({}) {}", expr(condition), nested(then_branch));
+            if let Some(else_branch) = else_branch {
+                source += &format!(" else {}", nested(else_branch));
+            }
+            source
+        }
+        Stmt::WhileStmt {
+            condition,
+            body,
+            increment: None,
+            ..
+        } => format!("while ({}) {}", expr(condition), nested(body)),
+        Stmt::WhileStmt {
+            condition,
+            body,
+            increment: Some(increment),
+            ..
+        } => format!(
+            "for (; {}; {}) {}",
+            expr(condition),
+            expr(increment),
+            nested(body)
+        ),
+        Stmt::Break { .. } => "break;".to_string(),
+        Stmt::Continue { .. } => "continue;".to_string(),
+        Stmt::Function {
+            name: fun,
+            params,
+            body,
+        } => format!(
+            "fun {}({}) {}",
+            name(fun),
+            params_list(params),
+            block(body, level, rewrite)
+        ),
+        Stmt::ReturnStmt { value: None, .. } => "return;".to_string(),
+        Stmt::ReturnStmt {
+            value: Some(value), ..
+        } => format!("return {};", expr(value)),
+        Stmt::Class {
+            name: class,
+            methods,
+            static_methods,
+            superclass,
+        } => {
+            let superclass = match superclass {
+                Some(superclass) => format!(" : {}", expr(superclass)),
+                None => String::new(),
+            };
+            format!(
+                "class {}{superclass} {}",
+                name(class),
+                class_body(methods, static_methods, level, rewrite)
+            )
+        }
+        Stmt::Import { name } => format!("import {};", name.name),
+        Stmt::Extend {
+            class,
+            methods,
+            static_methods,
+        } => format!(
+            "extend {} {}",
+            expr(class),
+            class_body(methods, static_methods, level, rewrite)
+        ),
+        Stmt::With {
+            name,
+            initializer,
+            body,
+            ..
+        } => format!(
+            "with (var {} = {}) {}",
+            name.name,
+            expr(initializer),
+            nested(body)
+        ),
+    };
+}
+
+fn block(stmts: &[Box<Stmt>], level: usize, rewrite: &dyn Rewrite) -> String {
+    let mut source = "{\n".to_string();
+    for stmt in stmts {
+        let stmt = self::stmt(stmt, level + 1, rewrite);
+        if !stmt.is_empty() {
+            source += &format!("{}{stmt}\n", INDENT.repeat(level + 1));
+        }
+    }
+
+    return source + &INDENT.repeat(level) + "}";
+}
+
+fn class_body(
+    methods: &[Box<Stmt>],
+    static_methods: &[Box<Stmt>],
+    level: usize,
+    rewrite: &dyn Rewrite,
+) -> String {
+    let methods = methods
+        .iter()
+        .map(|method| ("", method))
+        .chain(static_methods.iter().map(|method| ("static ", method)));
+
+    let mut source = "{\n".to_string();
+    for (modifier, method) in methods {
+        if let Stmt::Function { name, params, body } = method.as_ref() {
+            source += &format!(
+                "{}{modifier}{}({}) {}\n",
+                INDENT.repeat(level + 1),
+                name.name,
+                params_list(params),
+                block(body, level + 1, rewrite)
+            );
+        }
+    }
+
+    return source + &INDENT.repeat(level) + "}";
+}
+
+fn params_list(params: &[Token]) -> String {
+    let params: Vec<&str> = params.iter().map(|param| param.name.as_str()).collect();
+    return params.join(", ");
+}
+
+/// Writes `expr`, with the bodies of anonymous functions indented one more
+/// `level`.
+pub fn expr(expr: &Expr, level: usize, rewrite: &dyn Rewrite) -> String {
+    if let Some(source) = rewrite.expr(expr, level) {
+        return source;
+    }
+
+    let write = |expr: &Expr| self::expr(expr, level, rewrite);
+    let write_all = |exprs: &[Expr]| {
+        return exprs.iter().map(write).collect::<Vec<String>>().join(", ");
+    };
+
+    return match expr {
+        Expr::Literal {
+            value: Literal::Decimal(value),
+            ..
+        } => format!("{value}d"),
+        Expr::Literal { value, .. } => value.repr(),
+        Expr::Variable { name, .. } => name.name.clone(),
+        Expr::This { .. } => "this".to_string(),
+        Expr::Super { method, .. } => format!("super.{}", method.name),
+        Expr::AnonFunction {
+            arguments, body, ..
+        } => format!(
+            "fun ({}) {}",
+            params_list(arguments),
+            block(body, level, rewrite)
+        ),
+        Expr::Grouping { expression, .. } => format!("({})", write(expression)),
+        Expr::Assign { name, value, .. } => format!("{} = {}", name.name, write(value)),
+        Expr::Binary {
+            left,
+            operator,
+            right,
+            ..
+        }
+        | Expr::Logical {
+            left,
+            operator,
+            right,
+            ..
+        } => format!("{} {} {}", write(left), operator.name, write(right)),
+        Expr::Unary {
+            operator, right, ..
+        } => format!("{}{}", operator.name, write(right)),
+        Expr::Call {
+            callee, arguments, ..
+        } => format!("{}({})", write(callee), write_all(arguments)),
+        Expr::Get { object, name, .. } => format!("{}.{}", write(object), name.name),
+        Expr::Set {
+            object,
+            name,
+            value,
+            ..
+        } => format!("{}.{} = {}", write(object), name.name, write(value)),
+        Expr::Index { object, index, .. } => format!("{}[{}]", write(object), write(index)),
+        Expr::IndexSet {
+            object,
+            index,
+            value,
+            ..
+        } => format!("{}[{}] = {}", write(object), write(index), write(value)),
+        Expr::Array { elements, .. } => format!("[{}]", write_all(elements)),
+        Expr::Map { entries, .. } => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", write(key), write(value)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    };
+}
@@ -0,0 +1,217 @@
+use crate::{
+    environment::Environment, expr::Literal, interpreter::Interpreter, program::Program, stmt::Stmt,
+};
+
+enum Frame<'a> {
+    /// Statements of the program, of a block or a branch. `enclosing` is
+    /// the environment to restore when they are done.
+    Statements {
+        stmts: Vec<&'a Stmt>,
+        next: usize,
+        enclosing: Option<Environment>,
+    },
+    /// A loop whose body ran, the next step runs its increment and checks
+    /// the condition again.
+    Loop { stmt: &'a Stmt },
+}
+
+/// Runs a program one statement at a time, see [`Interpreter::stepper`].
+///
+/// Blocks, `if` and `while` are entered rather than run in one step: the
+/// step on an `if` or a `while` evaluates the condition and the statements
+/// of the chosen branch or body follow. Function calls and `with`
+/// statements run to completion within a single step.
+pub struct Stepper<'a> {
+    interpreter: &'a mut Interpreter,
+    frames: Vec<Frame<'a>>,
+}
+
+impl<'a> Stepper<'a> {
+    pub fn new(interpreter: &'a mut Interpreter, program: &'a Program) -> Self {
+        interpreter.resolve(program.locals().clone());
+
+        let mut stepper = Self {
+            interpreter,
+            frames: vec![Frame::Statements {
+                stmts: program.stmts().iter().collect(),
+                next: 0,
+                enclosing: None,
+            }],
+        };
+        stepper.leave_finished();
+        return stepper;
+    }
+
+    /// The statement the next step runs, `None` once the program is done.
+    pub fn current(&self) -> Option<&'a Stmt> {
+        return match self.frames.last()? {
+            Frame::Statements { stmts, next, .. } => stmts.get(*next).copied(),
+            Frame::Loop { stmt, .. } => Some(stmt),
+        };
+    }
+
+    /// The scope the next step runs in. Its `values` and `local_values`
+    /// are a snapshot of the variables between steps.
+    pub fn environment(&self) -> &Environment {
+        return &self.interpreter.environment;
+    }
+
+    pub fn is_done(&self) -> bool {
+        return self.frames.is_empty();
+    }
+
+    /// Runs the current statement and returns it, or `None` when the
+    /// program is already done. After an error the program is done.
+    pub fn step(&mut self) -> Result<Option<&'a Stmt>, String> {
+        let Some(stmt) = self.current() else {
+            return Ok(None);
+        };
+
+        if let Err(err) = self.run(stmt) {
+            self.unwind(0);
+            return Err(err);
+        }
+
+        self.leave_finished();
+        return Ok(Some(stmt));
+    }
+
+    fn run(&mut self, stmt: &'a Stmt) -> Result<(), String> {
+        if let Some(Frame::Loop { .. }) = self.frames.last() {
+            return self.run_loop(stmt, true);
+        }
+
+        if let Some(Frame::Statements { next, .. }) = self.frames.last_mut() {
+            *next += 1;
+        }
+
+        match stmt {
+            Stmt::Block { statements } => {
+                let enclosing = self.interpreter.environment.clone();
+                self.interpreter.environment = enclosing.enclose();
+                self.frames.push(Frame::Statements {
+                    stmts: statements.iter().map(|stmt| stmt.as_ref()).collect(),
+                    next: 0,
+                    enclosing: Some(enclosing),
+                });
+            }
+            Stmt::IfStmt {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let truth_value = condition.evaluate(self.interpreter.environment.clone())?;
+
+                let branch = if truth_value.is_truthy() == Literal::True {
+                    Some(then_branch)
+                } else {
+                    else_branch.as_ref()
+                };
+                if let Some(branch) = branch {
+                    self.push_branch(branch);
+                }
+            }
+            Stmt::WhileStmt { .. } => {
+                self.frames.push(Frame::Loop { stmt });
+                self.run_loop(stmt, false)?;
+            }
+            Stmt::Break { .. } => {
+                self.unwind_to_loop();
+                self.frames.pop();
+            }
+            Stmt::Continue { .. } => self.unwind_to_loop(),
+            _ => {
+                self.interpreter.execute(stmt)?;
+
+                // A `break` or `continue` in the body of a `with` is only
+                // recorded by the interpreter
+                if self.interpreter.specials.remove("break").is_some() {
+                    self.unwind_to_loop();
+                    self.frames.pop();
+                } else if self.interpreter.specials.remove("continue").is_some() {
+                    self.unwind_to_loop();
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Checks the condition of the loop on top of the frames, after running
+    /// its increment when `repeat`, and enters the body.
+    fn run_loop(&mut self, stmt: &'a Stmt, repeat: bool) -> Result<(), String> {
+        let Stmt::WhileStmt {
+            condition,
+            body,
+            increment,
+            ..
+        } = stmt
+        else {
+            return Err("Only a while statement can be a loop".to_string());
+        };
+
+        let environment = self.interpreter.environment.clone();
+        if let (true, Some(increment)) = (repeat, increment) {
+            increment.evaluate(environment.clone())?;
+        }
+
+        if condition.evaluate(environment)?.is_truthy() == Literal::True {
+            self.push_branch(body);
+        } else {
+            self.frames.pop();
+        }
+
+        return Ok(());
+    }
+
+    fn push_branch(&mut self, stmt: &'a Stmt) {
+        self.frames.push(Frame::Statements {
+            stmts: vec![stmt],
+            next: 0,
+            enclosing: None,
+        });
+    }
+
+    /// Drops the frames in the innermost loop, leaving the loop on top.
+    fn unwind_to_loop(&mut self) {
+        let depth = self
+            .frames
+            .iter()
+            .rposition(|frame| matches!(frame, Frame::Loop { .. }))
+            .map_or(0, |idx| idx + 1);
+        self.unwind(depth);
+    }
+
+    /// Drops frames until `depth` are left, restoring the environment of
+    /// the blocks they leave.
+    fn unwind(&mut self, depth: usize) {
+        while self.frames.len() > depth {
+            if let Some(Frame::Statements {
+                enclosing: Some(enclosing),
+                ..
+            }) = self.frames.pop()
+            {
+                self.interpreter.environment = enclosing;
+            }
+        }
+    }
+
+    /// Drops the frames whose statements all ran.
+    fn leave_finished(&mut self) {
+        while let Some(Frame::Statements { stmts, next, .. }) = self.frames.last() {
+            if *next < stmts.len() {
+                break;
+            }
+            self.unwind(self.frames.len() - 1);
+        }
+    }
+}
+
+impl<'a> Iterator for Stepper<'a> {
+    type Item = Result<&'a Stmt, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.step().transpose();
+    }
+}
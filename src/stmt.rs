@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::{expr::Expr, token::Token};
 
 #[derive(Debug, PartialEq, Clone)]
@@ -17,13 +19,23 @@ pub enum Stmt {
         statements: Vec<Box<Stmt>>,
     },
     IfStmt {
+        keyword: Token,
         condition: Expr,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
     },
     WhileStmt {
+        keyword: Token,
         condition: Expr,
         body: Box<Stmt>,
+        /// The increment of a `for` loop, also run after a `continue`.
+        increment: Option<Expr>,
+    },
+    Break {
+        keyword: Token,
+    },
+    Continue {
+        keyword: Token,
     },
     Function {
         name: Token,
@@ -37,18 +49,37 @@ pub enum Stmt {
     Class {
         name: Token,
         methods: Vec<Box<Stmt>>,
+        static_methods: Vec<Box<Stmt>>,
         superclass: Option<Expr>,
     },
+    Import {
+        name: Token,
+    },
+    /// Adds methods to an existing class.
+    Extend {
+        class: Expr,
+        methods: Vec<Box<Stmt>>,
+        static_methods: Vec<Box<Stmt>>,
+    },
+    /// Runs `body` with the variable `name` and calls its `close` method
+    /// when leaving it, also because of an error.
+    With {
+        keyword: Token,
+        name: Token,
+        initializer: Expr,
+        body: Box<Stmt>,
+    },
 }
 
-impl ToString for Stmt {
-    fn to_string(&self) -> String {
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Stmt::Expression { expression } => expression.to_string(),
-            Stmt::Print { expression, .. } => format!("(print {})", expression.to_string()),
-            Stmt::Var { name, .. } => format!("(var {})", name.name),
+            Stmt::Expression { expression } => write!(f, "{expression}"),
+            Stmt::Print { expression, .. } => write!(f, "(print {expression})"),
+            Stmt::Var { name, .. } => write!(f, "(var {})", name.name),
             Self::Block { statements } => {
-                return format!(
+                return write!(
+                    f,
                     "(block {:?})",
                     statements
                         .iter()
@@ -56,11 +87,19 @@ impl ToString for Stmt {
                         .collect::<String>()
                 );
             }
-            Stmt::IfStmt { .. } => todo!(),
-            Stmt::WhileStmt { .. } => todo!(),
-            Stmt::Function { .. } => todo!(),
-            Stmt::ReturnStmt { .. } => todo!(),
-            Stmt::Class { .. } => todo!(),
+            Stmt::IfStmt { condition, .. } => write!(f, "(if {condition})"),
+            Stmt::WhileStmt { condition, .. } => write!(f, "(while {condition})"),
+            Stmt::Function { name, .. } => write!(f, "(fun {})", name.name),
+            Stmt::ReturnStmt { value, .. } => match value {
+                Some(value) => write!(f, "(return {value})"),
+                None => write!(f, "(return)"),
+            },
+            Stmt::Class { name, .. } => write!(f, "(class {})", name.name),
+            Stmt::Break { .. } => write!(f, "(break)"),
+            Stmt::Continue { .. } => write!(f, "(continue)"),
+            Stmt::Import { name } => write!(f, "(import {})", name.name),
+            Stmt::Extend { class, .. } => write!(f, "(extend {class})"),
+            Stmt::With { name, .. } => write!(f, "(with {})", name.name),
         }
     }
 }
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+    Method,
+    Parameter,
+    Class,
+    Import,
+    /// The variable of a `with` statement.
+    Resource,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SymbolKind::Variable => "variable",
+            SymbolKind::Function => "function",
+            SymbolKind::Method => "method",
+            SymbolKind::Parameter => "parameter",
+            SymbolKind::Class => "class",
+            SymbolKind::Import => "import",
+            SymbolKind::Resource => "resource",
+        };
+        return write!(f, "{name}");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A use of a name in an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub expr_id: usize,
+    pub line: usize,
+    pub access: Access,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    pub scope: usize,
+    pub references: Vec<Reference>,
+}
+
+impl Declaration {
+    pub fn is_read(&self) -> bool {
+        return self
+            .references
+            .iter()
+            .any(|reference| reference.access == Access::Read);
+    }
+}
+
+/// A block, function body or class body. The scope with id 0 holds the
+/// globals and is the only one without a parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    pub parent: Option<usize>,
+    pub declarations: Vec<usize>,
+}
+
+/// Every declaration of a program with the references to it, as found by
+/// the resolver. Declarations and scopes are identified by their index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolTable {
+    pub scopes: Vec<Scope>,
+    pub declarations: Vec<Declaration>,
+    /// References to names declared outside of the program, e.g. natives.
+    pub unresolved: Vec<Reference>,
+    // Expression id to the declaration it refers to
+    definitions: HashMap<usize, usize>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        return Self {
+            scopes: vec![Scope {
+                parent: None,
+                declarations: vec![],
+            }],
+            declarations: vec![],
+            unresolved: vec![],
+            definitions: HashMap::new(),
+        };
+    }
+}
+
+impl SymbolTable {
+    pub fn add_scope(&mut self, parent: usize) -> usize {
+        self.scopes.push(Scope {
+            parent: Some(parent),
+            declarations: vec![],
+        });
+        return self.scopes.len() - 1;
+    }
+
+    pub fn declare(&mut self, scope: usize, name: &str, kind: SymbolKind, line: usize) -> usize {
+        let id = self.declarations.len();
+        self.declarations.push(Declaration {
+            name: name.to_string(),
+            kind,
+            line,
+            scope,
+            references: vec![],
+        });
+        self.scopes[scope].declarations.push(id);
+        return id;
+    }
+
+    pub fn add_reference(&mut self, declaration: usize, reference: Reference) {
+        self.definitions.insert(reference.expr_id, declaration);
+        self.declarations[declaration].references.push(reference);
+    }
+
+    /// The declaration the expression with id `expr_id` refers to.
+    pub fn definition(&self, expr_id: usize) -> Option<&Declaration> {
+        let id = self.definitions.get(&expr_id)?;
+        return self.declarations.get(*id);
+    }
+
+    /// The first declaration of the global `name`.
+    pub fn global(&self, name: &str) -> Option<usize> {
+        return self.scopes[0]
+            .declarations
+            .iter()
+            .copied()
+            .find(|id| self.declarations[*id].name == name);
+    }
+
+    pub fn children(&self, scope: usize) -> impl Iterator<Item = usize> + '_ {
+        return (0..self.scopes.len()).filter(move |id| self.scopes[*id].parent == Some(scope));
+    }
+
+    fn fmt_scope(&self, f: &mut fmt::Formatter<'_>, scope: usize) -> fmt::Result {
+        match self.scopes[scope].parent {
+            Some(parent) => writeln!(f, "scope {scope} in scope {parent}")?,
+            None => writeln!(f, "scope {scope}")?,
+        }
+
+        for id in &self.scopes[scope].declarations {
+            let declaration = &self.declarations[*id];
+            write!(
+                f,
+                "{} '{}' at line {}",
+                declaration.kind, declaration.name, declaration.line
+            )?;
+            for reference in &declaration.references {
+                let access = match reference.access {
+                    Access::Read => "read",
+                    Access::Write => "written",
+                };
+                write!(f, ", {access} at line {}", reference.line)?;
+            }
+            writeln!(f)?;
+        }
+
+        for child in self.children(scope) {
+            self.fmt_scope(f, child)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl fmt::Display for SymbolTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_scope(f, 0)?;
+
+        for reference in &self.unresolved {
+            writeln!(
+                f,
+                "unresolved '{}' at line {}",
+                reference.name, reference.line
+            )?;
+        }
+
+        return Ok(());
+    }
+}
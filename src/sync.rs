@@ -0,0 +1,106 @@
+//! Shared ownership used by the runtime values and environments.
+//!
+//! By default `Shared<Lock<T>>` is an `Rc<RefCell<T>>`. With the `sync`
+//! feature it becomes an `Arc<RwLock<T>>`, which makes the interpreter
+//! `Send + Sync` at the cost of some locking overhead.
+//!
+//! A `Lock` can also be frozen by `freeze()`. It is still writable from
+//! Rust, the values living in it check the flag before a script modifies
+//! them.
+
+#[cfg(not(feature = "sync"))]
+mod imp {
+    use std::cell::{Cell, Ref, RefCell, RefMut};
+
+    pub type Shared<T> = std::rc::Rc<T>;
+
+    /// Bound required from host closures registered as natives.
+    pub trait MaybeSendSync {}
+
+    impl<T: ?Sized> MaybeSendSync for T {}
+
+    #[derive(Debug, Default)]
+    pub struct Lock<T> {
+        value: RefCell<T>,
+        frozen: Cell<bool>,
+    }
+
+    impl<T> Lock<T> {
+        pub fn new(value: T) -> Self {
+            return Self {
+                value: RefCell::new(value),
+                frozen: Cell::new(false),
+            };
+        }
+
+        pub fn borrow(&self) -> Ref<'_, T> {
+            return self.value.borrow();
+        }
+
+        pub fn borrow_mut(&self) -> RefMut<'_, T> {
+            return self.value.borrow_mut();
+        }
+
+        pub fn freeze(&self) {
+            self.frozen.set(true);
+        }
+
+        pub fn is_frozen(&self) -> bool {
+            return self.frozen.get();
+        }
+    }
+}
+
+#[cfg(feature = "sync")]
+mod imp {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock, RwLockReadGuard, RwLockWriteGuard,
+    };
+
+    pub type Shared<T> = std::sync::Arc<T>;
+
+    /// Bound required from host closures registered as natives.
+    pub trait MaybeSendSync: Send + Sync {}
+
+    impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+    #[derive(Debug, Default)]
+    pub struct Lock<T> {
+        value: RwLock<T>,
+        frozen: AtomicBool,
+    }
+
+    impl<T> Lock<T> {
+        pub fn new(value: T) -> Self {
+            return Self {
+                value: RwLock::new(value),
+                frozen: AtomicBool::new(false),
+            };
+        }
+
+        pub fn borrow(&self) -> RwLockReadGuard<'_, T> {
+            return self.value.read().expect("Lock poisoned");
+        }
+
+        pub fn borrow_mut(&self) -> RwLockWriteGuard<'_, T> {
+            return self.value.write().expect("Lock poisoned");
+        }
+
+        pub fn freeze(&self) {
+            self.frozen.store(true, Ordering::SeqCst);
+        }
+
+        pub fn is_frozen(&self) -> bool {
+            return self.frozen.load(Ordering::SeqCst);
+        }
+    }
+
+    const _: fn() = || {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<crate::interpreter::Interpreter>();
+        assert_send_sync::<crate::program::Program>();
+    };
+}
+
+pub use imp::*;
@@ -1,8 +1,13 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+
 use crate::token_type::TokenType;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Literal {
     Number(f64),
+    Decimal(Decimal),
     String(String),
     Identifier(String),
 }
@@ -13,21 +18,31 @@ pub struct Token {
     pub name: String,
     pub literal: Option<Literal>,
     pub line: usize,
+    /// Column of the first character, on the line where the token starts
+    /// even when it is a string spanning several lines.
+    pub column: usize,
 }
 
 impl Token {
-    pub fn new(token: TokenType, value: &str, literal: Option<Literal>, line: usize) -> Self {
+    pub fn new(
+        token: TokenType,
+        value: &str,
+        literal: Option<Literal>,
+        line: usize,
+        column: usize,
+    ) -> Self {
         return Self {
             token_type: token,
             name: value.to_string(),
             literal,
             line,
+            column,
         };
     }
 }
 
-impl ToString for Token {
-    fn to_string(&self) -> String {
-        return format!("{:?} '{}'", self.token_type, self.name);
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{:?} '{}'", self.token_type, self.name);
     }
 }
@@ -5,6 +5,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -28,24 +30,31 @@ pub enum TokenType {
     Identifier,
     String,
     Number,
+    Decimal,
 
     // Keywords.
     And,
+    Break,
     Class,
+    Continue,
     Else,
+    Extend,
     False,
     Fun,
     For,
     If,
+    Import,
     Nil,
     Or,
     Print,
     Return,
+    Static,
     Super,
     This,
     True,
     Var,
     While,
+    With,
 
     EOF,
 }
@@ -0,0 +1,27 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{interpreter::Interpreter, output::Output};
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct RunResult {
+    pub output: String,
+    pub errors: String,
+}
+
+/// Runs `source` in a fresh interpreter and returns everything it printed
+/// together with the error message, if any.
+#[wasm_bindgen]
+pub fn run(source: &str) -> RunResult {
+    let output = Output::buffer();
+    let mut interpreter = Interpreter::with_output(output.clone());
+
+    let errors = match interpreter.run(source) {
+        Ok(_) => String::new(),
+        Err(err) => err,
+    };
+
+    return RunResult {
+        output: output.take(),
+        errors,
+    };
+}
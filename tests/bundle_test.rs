@@ -0,0 +1,88 @@
+use std::{fs, path::Path};
+
+use programming_language::{bundle::bundle, interpreter::Interpreter, output::Output};
+
+const SCRIPT: &str = "tests/bundle/main.lox";
+
+fn run(src: &str) -> String {
+    let output = Output::buffer();
+    let mut interpreter = Interpreter::with_output(output.clone());
+    interpreter
+        .environment
+        .modules()
+        .set_base_dir(Path::new(SCRIPT).parent().unwrap().to_path_buf());
+
+    interpreter.run(src).unwrap();
+    return output.take();
+}
+
+/// Bundles `script` after writing it and `modules` to a fresh directory.
+fn bundle_files(dir: &str, script: &str, modules: &[(&str, &str)]) -> Result<String, String> {
+    let dir = std::env::temp_dir().join(dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (name, src) in modules {
+        fs::write(dir.join(format!("{name}.lox")), src).unwrap();
+    }
+    fs::write(dir.join("main.lox"), script).unwrap();
+
+    return bundle(&dir.join("main.lox"));
+}
+
+#[test]
+fn bundle_runs_like_the_script() {
+    let bundled = bundle(Path::new(SCRIPT)).unwrap();
+
+    assert!(!bundled.contains("import"));
+    assert_eq!(run(&bundled), run(&fs::read_to_string(SCRIPT).unwrap()));
+}
+
+#[test]
+fn bundle_renames_module_bindings() {
+    let bundled = bundle(Path::new(SCRIPT)).unwrap();
+
+    assert!(bundled.contains("var units__suffix = \"cm\";"));
+    assert!(bundled.contains("var geometry__scale = 2;"));
+    assert!(bundled.contains("var scale = 10;"));
+    assert!(bundled.contains("units__label(this.x)"));
+    // Modules come first, in the order they are first imported
+    assert!(bundled.find("// Module units") < bundled.find("// Module geometry"));
+}
+
+#[test]
+fn bundle_rejects_what_it_cannot_rename() {
+    let err = bundle_files(
+        "bundle_missing_member",
+        "import util;\nprint(\"{}\", util.missing);",
+        &[("util", "var present = 1;")],
+    );
+    assert_eq!(
+        err,
+        Err("Line 2: Module 'util' has no member 'missing'".to_string())
+    );
+
+    let err = bundle_files(
+        "bundle_module_value",
+        "import util;\nvar module = util;",
+        &[("util", "var present = 1;")],
+    );
+    assert_eq!(
+        err,
+        Err("Line 2: Module 'util' can only be used to access its members in a bundle".to_string())
+    );
+
+    let err = bundle_files(
+        "bundle_circular",
+        "import a;",
+        &[("a", "import b;"), ("b", "import a;")],
+    );
+    assert_eq!(
+        err,
+        Err("In module 'a': In module 'b': Circular import of module 'a'".to_string())
+    );
+}
+
+#[test]
+fn bundle_keeps_imports_of_host_modules() {
+    let bundled = bundle_files("bundle_host_module", "import host;\nhost.run();", &[]);
+    assert_eq!(bundled, Ok("import host;\nhost.run();\n".to_string()));
+}
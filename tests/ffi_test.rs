@@ -0,0 +1,69 @@
+#![cfg(feature = "cdylib")]
+
+use std::{
+    ffi::{c_void, CStr, CString},
+    ptr, slice,
+};
+
+use programming_language::ffi::{
+    lang_eval, lang_free, lang_get_string_result, lang_new, lang_register_callback,
+    LangInterpreter, LangValue, LangValueKind,
+};
+
+fn result(lang: *const LangInterpreter) -> String {
+    let result = unsafe { CStr::from_ptr(lang_get_string_result(lang)) };
+    return result.to_str().unwrap().to_string();
+}
+
+extern "C" fn add(user_data: *mut c_void, args: *const LangValue, argc: usize) -> LangValue {
+    let offset = unsafe { *(user_data as *const f64) };
+    let args = unsafe { slice::from_raw_parts(args, argc) };
+
+    return LangValue {
+        kind: LangValueKind::Number,
+        boolean: false,
+        number: args[0].number + args[1].number + offset,
+        string: ptr::null(),
+    };
+}
+
+#[test]
+fn evaluates_scripts_and_calls_callbacks() {
+    let offset = 0.5f64;
+    let lang = lang_new();
+
+    let name = CString::new("add").unwrap();
+    let user_data = &offset as *const f64 as *mut c_void;
+    let status = unsafe { lang_register_callback(lang, name.as_ptr(), 2, Some(add), user_data) };
+    assert_eq!(status, 0);
+
+    let source = CString::new("var a = 1; print(\"{}\", add(a, 2));").unwrap();
+    assert_eq!(unsafe { lang_eval(lang, source.as_ptr()) }, 0);
+    assert_eq!(result(lang), "3.5\n");
+
+    let source = CString::new("print(\"{}\", a);").unwrap();
+    assert_eq!(unsafe { lang_eval(lang, source.as_ptr()) }, 0);
+    assert_eq!(result(lang), "1\n");
+
+    let source = CString::new("undefined();").unwrap();
+    assert_eq!(unsafe { lang_eval(lang, source.as_ptr()) }, 1);
+    assert!(result(lang).contains("undefined"));
+
+    unsafe { lang_free(lang) };
+}
+
+#[test]
+fn rejects_null_arguments() {
+    let lang = lang_new();
+    let name = CString::new("missing").unwrap();
+
+    let status = unsafe { lang_register_callback(lang, name.as_ptr(), 0, None, ptr::null_mut()) };
+    assert_eq!(status, -1);
+    let status =
+        unsafe { lang_register_callback(lang, ptr::null(), 0, Some(add), ptr::null_mut()) };
+    assert_eq!(status, -1);
+    assert_eq!(unsafe { lang_eval(lang, ptr::null()) }, -1);
+    assert_eq!(unsafe { lang_eval(ptr::null_mut(), name.as_ptr()) }, -1);
+
+    unsafe { lang_free(lang) };
+}
@@ -32,7 +32,7 @@ fn execute_tests() {
         println!("{}", msg);
     }
 
-    if errors.len() > 0 {
+    if !errors.is_empty() {
         panic!(
             "Errors:\n\n{}",
             errors.join("\n\n-------------------------------------------\n")
@@ -46,26 +46,31 @@ fn run_test(file: DirEntry) -> Result<(), String> {
     let lines = contents.split("\n").collect::<Vec<&str>>();
 
     let mut test_code = vec![];
+    let mut args = vec![];
 
     let mut idx = None;
     for (i, line) in lines.iter().enumerate() {
         if line.starts_with("// --- Expected") {
             idx = Some(i);
             break;
+        } else if let Some(flags) = line.strip_prefix("// --- Args") {
+            args.extend(flags.split_whitespace());
         } else if !line.starts_with("// --- Test") {
-            test_code.push(line.clone());
+            test_code.push(*line);
         }
     }
 
-    let idx = idx.expect(&format!(
-        "{:#?}: No expected section in test case definition",
-        file.file_name()
-    ));
+    let idx = idx.unwrap_or_else(|| {
+        panic!(
+            "{:#?}: No expected section in test case definition",
+            file.file_name()
+        )
+    });
 
     let mut expected_output = vec![];
 
     for line in &lines[idx + 1..] {
-        if line.len() > 0 {
+        if !line.is_empty() {
             let string = line.to_string();
             expected_output.push((string[3..]).to_string());
         }
@@ -75,14 +80,23 @@ fn run_test(file: DirEntry) -> Result<(), String> {
 
     let output = Command::new("cargo")
         .arg("run")
+        .arg("--")
+        .args(args)
         .arg("e")
         .arg(input)
         .output()
         .unwrap();
 
-    let lines = std::str::from_utf8(output.stdout.as_slice())
-        .unwrap()
-        .split("\n")
+    // Resolver diagnostics go to stderr, next to cargo's own messages
+    let stderr = std::str::from_utf8(output.stderr.as_slice()).unwrap();
+    let lines = stderr
+        .lines()
+        .filter(|line| line.starts_with("Warning: ") || line.starts_with("Error: "))
+        .chain(
+            std::str::from_utf8(output.stdout.as_slice())
+                .unwrap()
+                .split("\n"),
+        )
         .collect::<Vec<&str>>();
 
     if !(lines.len() == expected_output.len() || lines.len() == expected_output.len() + 1) {
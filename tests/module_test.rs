@@ -0,0 +1,49 @@
+use programming_language::{
+    expr::Literal, expr::NativeFunctionImpl, interpreter::Interpreter, output::Output,
+};
+
+fn interpreter() -> (Interpreter, Output) {
+    let output = Output::buffer();
+    let mut interpreter = Interpreter::with_output(output.clone());
+    interpreter.register_module(
+        "util",
+        vec![
+            NativeFunctionImpl::new("double", 1, |args| {
+                return match &args[0] {
+                    Literal::Number(x) => Ok(Literal::Number(x * 2.0)),
+                    other => Err(format!("double expected a Number but got {other}")),
+                };
+            }),
+            NativeFunctionImpl::new("name", 0, |_args| {
+                return Ok(Literal::String("util".to_string()));
+            }),
+        ],
+    );
+
+    return (interpreter, output);
+}
+
+#[test]
+fn host_registered_module_can_be_imported() {
+    let (mut interpreter, output) = interpreter();
+
+    interpreter
+        .run("import util; print(util.double(21)); print(util.name()); print(util);")
+        .unwrap();
+    assert_eq!(output.take(), "42\nutil\nModule 'util'\n");
+
+    let err = interpreter.run("util.double(\"a\");").unwrap_err();
+    assert_eq!(err, "double expected a Number but got a");
+}
+
+#[test]
+fn modules_are_truthy() {
+    let (mut interpreter, output) = interpreter();
+
+    interpreter
+        .run(
+            "import util; if (util) print(1); print(!util); print(util and 2); print(false or util);",
+        )
+        .unwrap();
+    assert_eq!(output.take(), "1\nfalse\n2\nModule 'util'\n");
+}
@@ -0,0 +1,30 @@
+//! Plugin loaded by tests/plugin_test.rs.
+
+use programming_language::{
+    declare_plugin,
+    expr::Literal,
+    plugin::{Plugin, PluginRegistry},
+};
+
+#[derive(Default)]
+struct Greeter;
+
+impl Plugin for Greeter {
+    fn register(&self, registry: &mut PluginRegistry) -> Result<(), String> {
+        registry.define_native("greet", 1, |args| {
+            return match &args[0] {
+                Literal::String(name) if name.is_empty() => Err("Nobody to greet".to_string()),
+                name => Ok(Literal::String(format!("Hello {name}"))),
+            };
+        });
+        registry.define_native("add", 2, |args| {
+            return match (&args[0], &args[1]) {
+                (Literal::Number(a), Literal::Number(b)) => Ok(Literal::Number(a + b)),
+                _ => Err("add expected two numbers".to_string()),
+            };
+        });
+        return registry.define_script("class Greeter { hi(name) { return greet(name); } }");
+    }
+}
+
+declare_plugin!(Greeter, Greeter::default);
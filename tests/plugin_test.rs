@@ -0,0 +1,50 @@
+#![cfg(feature = "plugins")]
+
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    path::PathBuf,
+    process::Command,
+};
+
+use programming_language::{interpreter::Interpreter, output::Output, plugin::load_plugin};
+
+/// Builds the plugin in tests/plugin and returns the path of the library.
+fn build_plugin() -> PathBuf {
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "build",
+            "--quiet",
+            "--manifest-path",
+            "tests/plugin/Cargo.toml",
+        ])
+        .args(["--target-dir", "target/plugin"])
+        .status()
+        .unwrap();
+    assert!(status.success(), "Cannot build the test plugin");
+
+    return PathBuf::from(format!(
+        "target/plugin/debug/{DLL_PREFIX}greeter_plugin{DLL_SUFFIX}"
+    ));
+}
+
+#[test]
+fn plugin_registers_natives_and_scripts() {
+    let output = Output::buffer();
+    let mut interpreter = Interpreter::with_output(output.clone());
+    load_plugin(&mut interpreter, &build_plugin()).unwrap();
+
+    interpreter
+        .run("print(greet(\"Ada\")); print(add(1, 2)); print(Greeter().hi(true));")
+        .unwrap();
+    assert_eq!(output.take(), "Hello Ada\n3\nHello true\n");
+
+    let err = interpreter.run("greet(\"\");").unwrap_err();
+    assert_eq!(err, "Nobody to greet");
+}
+
+#[test]
+fn loading_a_missing_plugin_fails() {
+    let mut interpreter = Interpreter::new();
+    let err = load_plugin(&mut interpreter, &PathBuf::from("missing.so")).unwrap_err();
+    assert!(err.starts_with("Cannot load plugin 'missing.so'"), "{err}");
+}
@@ -0,0 +1,130 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use programming_language::{interpreter::Interpreter, output::Output, server};
+
+fn interpreter() -> Interpreter {
+    return Interpreter::with_output(Output::buffer());
+}
+
+/// Sends the requests over one connection and returns the responses.
+fn send(stream: TcpStream, requests: &[&str]) -> Vec<String> {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+
+    let mut responses = vec![];
+    for request in requests {
+        write!(stream, "{request}\n.\n").unwrap();
+
+        let mut response = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == ".\n" {
+                break;
+            }
+            response += &line;
+        }
+        responses.push(response);
+    }
+
+    return responses;
+}
+
+#[test]
+fn respond_reports_output_and_errors() {
+    let mut interpreter = interpreter();
+
+    assert_eq!(
+        server::respond(&mut interpreter, "var x = 1;\nprint(\"{}\", x);"),
+        "1\n.\n"
+    );
+    assert_eq!(
+        server::respond(
+            &mut interpreter,
+            "print(\"{}\", x + 1);\nerror(\"failed\");"
+        ),
+        "2\nError: failed\n.\n"
+    );
+    assert_eq!(
+        server::respond(&mut interpreter, "print(\".5\");"),
+        "..5\n.\n"
+    );
+}
+
+#[test]
+fn respond_survives_interpreter_panics() {
+    let mut interpreter = interpreter();
+
+    assert_eq!(server::respond(&mut interpreter, "var x = 1;"), ".\n");
+    assert_eq!(
+        server::respond(
+            &mut interpreter,
+            "class A {}\n{\n    var y = 2;\n    print(\"{}\", y);\n    if (A) print(1);\n}"
+        ),
+        "2\nError: Internal interpreter error\n.\n"
+    );
+    assert_eq!(
+        server::respond(&mut interpreter, "print(\"{}\", x);"),
+        "1\n.\n"
+    );
+    // Back in the global scope, where the block's variable is gone
+    let response = server::respond(&mut interpreter, "print(\"{}\", y);");
+    assert!(
+        response.contains("Error: Undefined variable 'y'"),
+        "{response}"
+    );
+}
+
+#[test]
+fn serve_skips_connections_that_fail_to_be_accepted() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        return send(TcpStream::connect(address).unwrap(), &["print(1);"]);
+    });
+
+    let mut interpreter = interpreter();
+    let failed = std::iter::once(Err(io::Error::other("accept failed")));
+    server::serve(&mut interpreter, failed.chain(listener.incoming().take(1)));
+
+    assert_eq!(client.join().unwrap(), ["1\n"]);
+}
+
+#[test]
+fn serve_keeps_the_interpreter_between_connections() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let first = send(
+            TcpStream::connect(address).unwrap(),
+            &[
+                "var count = 1;",
+                "count = count + 1;\nprint(\"{}\", count);",
+            ],
+        );
+        let second = send(
+            TcpStream::connect(address).unwrap(),
+            &["print(\"{}\", count);", "print(missing);"],
+        );
+        return (first, second);
+    });
+
+    let mut interpreter = interpreter();
+    server::serve(&mut interpreter, listener.incoming().take(2));
+
+    let (first, second) = client.join().unwrap();
+    assert_eq!(first, ["", "2\n"]);
+    assert_eq!(
+        second,
+        [
+            "2\n",
+            "Warning: Undefined variable 'missing' at line 1\nError: Undefined variable 'missing' at distance None\n"
+        ]
+    );
+}
@@ -0,0 +1,106 @@
+//! Runs `.lox` files written in the format of the canonical Lox test suite:
+//! every `// expect: <line>` comment is a line the script must print and an
+//! `// expect runtime error: <message>` comment is the error it must stop with.
+//!
+//! The files in _tests/spec_ always run; `LOX_SPEC_DIR=path cargo test` adds
+//! the files of another suite. Scripts are parsed in Lox compatibility mode.
+
+use std::{
+    env,
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+};
+
+use programming_language::{interpreter::Interpreter, output::Output, program::Program};
+
+const EXPECT: &str = "// expect: ";
+const EXPECT_RUNTIME_ERROR: &str = "// expect runtime error: ";
+
+#[test]
+fn execute_spec_tests() {
+    let mut files = vec![];
+    collect_files(Path::new("./tests/spec"), &mut files);
+    if let Ok(dir) = env::var("LOX_SPEC_DIR") {
+        collect_files(Path::new(&dir), &mut files);
+    }
+    files.sort();
+
+    let mut errors = vec![];
+    for file in &files {
+        let name = file.display().to_string();
+        match run_spec(file) {
+            Ok(_) => println!("Running {name:.<85}...ok"),
+            Err(msg) => {
+                println!("Running {name:.<85}...failed");
+                errors.push(format!("{name}: {msg}"));
+            }
+        }
+    }
+
+    println!("Ran {} spec tests", files.len());
+    if !errors.is_empty() {
+        panic!(
+            "Errors:\n\n{}",
+            errors.join("\n\n-------------------------------------------\n")
+        );
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = read_dir(dir).unwrap_or_else(|err| panic!("{}: {err}", dir.display()));
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            files.push(path);
+        }
+    }
+}
+
+fn run_spec(file: &Path) -> Result<(), String> {
+    let src = read_to_string(file).map_err(|err| err.to_string())?;
+
+    let mut expected_output = vec![];
+    let mut expected_error = None;
+    for line in src.lines() {
+        if let Some(idx) = line.find(EXPECT) {
+            expected_output.push(line[idx + EXPECT.len()..].trim_end().to_string());
+        } else if let Some(idx) = line.find(EXPECT_RUNTIME_ERROR) {
+            expected_error = Some(
+                line[idx + EXPECT_RUNTIME_ERROR.len()..]
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+    }
+
+    let output = Output::buffer();
+    let mut interpreter = Interpreter::builder()
+        .output(output.clone())
+        .lox_compat(true)
+        .build();
+    let program = Program::compile_in(&src, &interpreter.environment)
+        .map_err(|err| format!("Compile error: {err}"))?;
+    let result = interpreter.run_program(&program);
+
+    let output = output.take();
+    let actual_output = output.lines().collect::<Vec<&str>>();
+    if actual_output != expected_output {
+        return Err(format!(
+            "Output does not match\nExpected:\n{}\nActual:\n{}",
+            expected_output.join("\n"),
+            actual_output.join("\n")
+        ));
+    }
+
+    return match (result, expected_error) {
+        (Ok(_), None) => Ok(()),
+        (Err(err), Some(expected)) if err == expected => Ok(()),
+        (Err(err), Some(expected)) => {
+            Err(format!("Runtime error does not match: {err} != {expected}"))
+        }
+        (Err(err), None) => Err(format!("Unexpected runtime error: {err}")),
+        (Ok(_), Some(expected)) => Err(format!("Expected runtime error: {expected}")),
+    };
+}
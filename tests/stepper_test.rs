@@ -0,0 +1,117 @@
+use programming_language::{
+    expr::Literal, interpreter::Interpreter, output::Output, program::Program,
+};
+
+fn steps(src: &str) -> (Vec<String>, String) {
+    let output = Output::buffer();
+    let mut interpreter = Interpreter::with_output(output.clone());
+    let program = Program::compile(src).unwrap();
+
+    let steps = interpreter
+        .stepper(&program)
+        .map(|stmt| stmt.unwrap().to_string())
+        .collect();
+    return (steps, output.take());
+}
+
+#[test]
+fn steps_into_blocks_branches_and_loops() {
+    let (steps, output) = steps(
+        "var a = 1;
+        {
+            var b = 2;
+            if (a < b) print(\"less\"); else print(\"more\");
+        }
+        for (var i = 0; i < 3; i = i + 1) {
+            if (i == 1) continue;
+            if (i == 2) break;
+            print(\"{}\", i);
+        }",
+    );
+
+    assert_eq!(output, "less\n0\n");
+    assert_eq!(
+        steps,
+        [
+            "(var a)",
+            "(block \"(var b)(if (< (var a) (var b)))\")",
+            "(var b)",
+            "(if (< (var a) (var b)))",
+            "(print less)",
+            // The for loop is a block with the variable and a while
+            "(block \"(var i)(while (< (var i) 3))\")",
+            "(var i)",
+            "(while (< (var i) 3))",
+            "(block \"(if (== (var i) 1))(if (== (var i) 2))(print {})\")",
+            "(if (== (var i) 1))",
+            "(if (== (var i) 2))",
+            "(print {})",
+            "(while (< (var i) 3))",
+            "(block \"(if (== (var i) 1))(if (== (var i) 2))(print {})\")",
+            "(if (== (var i) 1))",
+            "(continue)",
+            "(while (< (var i) 3))",
+            "(block \"(if (== (var i) 1))(if (== (var i) 2))(print {})\")",
+            "(if (== (var i) 1))",
+            "(if (== (var i) 2))",
+            "(break)",
+        ]
+    );
+}
+
+#[test]
+fn exposes_the_environment_between_steps() {
+    let mut interpreter = Interpreter::with_output(Output::buffer());
+    let program = Program::compile("var a = 1; { var b = a + 1; print(\"{}\", b); }").unwrap();
+    let mut stepper = interpreter.stepper(&program);
+
+    assert_eq!(stepper.current().unwrap().to_string(), "(var a)");
+    stepper.step().unwrap();
+    stepper.step().unwrap();
+    stepper.step().unwrap();
+
+    assert_eq!(stepper.current().unwrap().to_string(), "(print {})");
+    let values = stepper.environment().local_values();
+    assert_eq!(values.get("b"), Some(&Literal::Number(2.0)));
+    assert_eq!(
+        stepper.environment().get_global("a"),
+        Some(Literal::Number(1.0))
+    );
+
+    stepper.step().unwrap();
+    assert!(stepper.is_done());
+    assert!(stepper.environment().local_values().is_empty());
+    assert!(stepper.step().unwrap().is_none());
+}
+
+#[test]
+fn stops_at_runtime_errors() {
+    let mut interpreter = Interpreter::with_output(Output::buffer());
+    let program = Program::compile("{ var a = 1; a(); print(\"unreachable\"); }").unwrap();
+
+    let results: Vec<_> = interpreter.stepper(&program).collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[2], Err("1 is not callable".to_string()));
+    assert!(interpreter.environment.local_values().is_empty());
+}
+
+#[test]
+fn leaves_loops_from_with_bodies() {
+    let (_, output) = steps(
+        "class Resource {
+            close() {
+                print(\"close\");
+            }
+        }
+        for (var i = 0; i < 3; i = i + 1) {
+            with (var r = Resource()) {
+                if (i == 0) continue;
+                break;
+            }
+            print(\"after {}\", i);
+        }
+        print(\"done\");",
+    );
+
+    assert_eq!(output, "close\nclose\ndone\n");
+}